@@ -0,0 +1,179 @@
+use std::{fs::{copy, read, read_dir, remove_file, rename, File}, io::{self, Write}, path::{Path, PathBuf}, process, sync::atomic::{AtomicU64, Ordering}};
+
+use crate::time::{format_timestamp, is_stamp, now_seconds, readable_stamp};
+
+pub const DEFAULT_BACKUP_COUNT: i8 = 5;
+
+const BACKUP_EXTENSION: &str = "bak";
+const QUARANTINE_EXTENSION: &str = "corrupt";
+const BACKUP_INTERVAL_SECONDS: u64 = 10 * 60;
+
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+pub struct Backup {
+    pub path: PathBuf,
+    pub stamp: String,
+}
+
+impl Backup {
+    pub fn label(&self) -> String {
+        readable_stamp(&self.stamp)
+    }
+}
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+// Writes next to the target first and renames over it, so a crash never leaves a half written file.
+// The temp name is unique per process and write, so the window and the command line can't trip over each other
+pub fn atomic_write(path: &Path, data: &[u8]) -> io::Result<()> {
+    let temp = sibling(path, &format!(".{}-{}.tmp", process::id(), TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)));
+    let written = File::create(&temp).and_then(|mut file| {
+        file.write_all(data)?;
+        file.sync_all()
+    });
+    if let Err(error) = written.and_then(|_| rename(&temp, path)) {
+        let _ = remove_file(&temp);
+        return Err(error);
+    }
+    sync_dir(path)
+}
+
+// The rename itself only survives a crash once the directory is flushed
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => File::open(parent)?.sync_all(),
+        _ => File::open(".")?.sync_all(),
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+// Newest first
+pub fn list_backups(path: &Path) -> Vec<Backup> {
+    list_stamped(path, BACKUP_EXTENSION)
+}
+
+fn list_stamped(path: &Path, extension: &str) -> Vec<Backup> {
+    let Some(file_name) = path.file_name().map(|name| name.to_string_lossy().to_string()) else { return Vec::new(); };
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let Ok(entries) = read_dir(&dir) else { return Vec::new(); };
+
+    let prefix = format!("{}.", file_name);
+    let suffix = format!(".{}", extension);
+    let mut backups: Vec<Backup> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let stamp = name.strip_prefix(&prefix)?.strip_suffix(&suffix)?.to_string();
+            // Other files with the same extension aren't ours, so they are neither listed nor pruned
+            is_stamp(&stamp).then(|| Backup { path: entry.path(), stamp })
+        })
+        .collect();
+
+    backups.sort_by(|a, b| b.stamp.cmp(&a.stamp));
+    backups
+}
+
+// Saves happen on every edit, so only take a new backup once the newest one is old enough
+pub fn create_backup(path: &Path, keep: i8) -> io::Result<()> {
+    let cutoff = format_timestamp(now_seconds().saturating_sub(BACKUP_INTERVAL_SECONDS));
+    match list_backups(path).first() {
+        Some(newest) if newest.stamp >= cutoff => { prune_backups(path, keep) },
+        _ => { snapshot(path, keep) },
+    }
+}
+
+pub fn snapshot(path: &Path, keep: i8) -> io::Result<()> {
+    if keep <= 0 || !path.exists() { return Ok(()); }
+
+    copy(path, sibling(path, &format!(".{}.{}", format_timestamp(now_seconds()), BACKUP_EXTENSION)))?;
+    prune_backups(path, keep)
+}
+
+pub fn prune_backups(path: &Path, keep: i8) -> io::Result<()> {
+    for backup in list_backups(path).into_iter().skip(keep.max(0) as usize) {
+        remove_file(backup.path)?;
+    }
+    Ok(())
+}
+
+// Sets a file that failed to load aside under a timestamped name, so the next save can't overwrite it.
+// A file that is opened again unchanged already has its copy, so that one is reused
+pub fn quarantine(path: &Path, keep_original: bool) -> io::Result<PathBuf> {
+    let target = sibling(path, &format!(".{}.{}", format_timestamp(now_seconds()), QUARANTINE_EXTENSION));
+    if keep_original {
+        let data = read(path)?;
+        if let Some(existing) = list_stamped(path, QUARANTINE_EXTENSION).into_iter().find(|copy| read(&copy.path).is_ok_and(|kept| kept == data)) {
+            return Ok(existing.path);
+        }
+        copy(path, &target)?;
+    }
+    else {
+        rename(path, &target)?;
+    }
+    Ok(target)
+}
+
+pub fn restore_backup(path: &Path, backup: &Path) -> io::Result<()> {
+    atomic_write(path, &read(backup)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, read_to_string, remove_dir_all, write};
+    use crate::test_support::workspace;
+
+    #[test]
+    fn backup_labels_are_readable() {
+        let backup = Backup { path: PathBuf::new(), stamp: String::from("20261018-142405") };
+
+        assert_eq!(backup.label(), "2026-10-18 14:24:05 UTC");
+    }
+
+    #[test]
+    fn only_timestamped_copies_count_as_backups() {
+        let dir = workspace("backups");
+        create_dir_all(&dir).unwrap();
+        let path = dir.join("rentdata.json");
+        for name in ["rentdata.json.old.bak", "rentdata.json.20261018-142405.bak", "rentdata.json.20261017-090000.bak", "rentdata.json.2026101x-142405.bak"] {
+            write(dir.join(name), "{}").unwrap();
+        }
+
+        let stamps: Vec<String> = list_backups(&path).into_iter().map(|backup| backup.stamp).collect();
+        assert_eq!(stamps, ["20261018-142405", "20261017-090000"]);
+
+        prune_backups(&path, 1).unwrap();
+        assert!(dir.join("rentdata.json.old.bak").exists());
+        assert!(dir.join("rentdata.json.20261018-142405.bak").exists());
+        assert!(!dir.join("rentdata.json.20261017-090000.bak").exists());
+
+        let _ = remove_dir_all(dir);
+    }
+
+    #[test]
+    fn atomic_writes_leave_no_temp_files() {
+        let dir = workspace("atomic");
+        create_dir_all(&dir).unwrap();
+        let path = dir.join("rentdata.json");
+
+        atomic_write(&path, b"first").unwrap();
+        atomic_write(&path, b"second").unwrap();
+
+        assert_eq!(read_to_string(&path).unwrap(), "second");
+        assert_eq!(read_dir(&dir).unwrap().count(), 1);
+
+        let _ = remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,341 @@
+use std::path::PathBuf;
+
+use jzon::JsonValue;
+
+use cost_analysis::objects::entry::Entry;
+use cost_analysis::objects::fields::Fields;
+use cost_analysis::store::{self, Portfolio};
+use cost_analysis::{backups, paths, spreadsheet, time};
+
+const JSON_FLAG: &str = "--json";
+const ID_KEY: &str = "id";
+const SPLITS_KEY: &str = "splits";
+const COMPUTED: [Fields; 5] = [Fields::EffectiveRent, Fields::RecurringFees, Fields::TotalRent, Fields::MoveInTotal, Fields::LeaseCost];
+
+pub const USAGE: &str = "Usage: cost_analysis [--data <path>] [<command>] [--json]
+
+Without a command the window opens. Commands:
+  list                          Show every listing
+  add <name> [field=value ...]  Add a listing and print its ID
+  set <id> field=value ...      Change fields on a listing
+  remove <id>                   Delete a listing
+  export [file.csv]             Write all listings as CSV, to stdout without a file
+  recalc                        Recalculate and rewrite the data file in the current format
+  migrate <file>                Copy everything to a new data file, SQLite when it ends in .sqlite or .db
+
+Fields are named like the table headers or the data file keys, e.g. \"Monthly Rent\"=1200 or beds=2.
+--json prints listings as JSON instead of a table.
+";
+
+pub struct Invocation {
+    pub command: String,
+    pub args: Vec<String>,
+    pub json: bool,
+}
+
+// Anything that isn't the data path or an output flag is the command and its arguments
+pub fn parse_args(args: &[String]) -> Result<Option<Invocation>, String> {
+    let (_, mut positional) = paths::split_data_arg(args)?;
+    let json = positional.iter().any(|arg| arg == JSON_FLAG);
+    positional.retain(|arg| arg != JSON_FLAG);
+
+    if positional.is_empty() { return Ok(None); }
+    let command = positional.remove(0);
+    Ok(Some(Invocation { command, args: positional, json }))
+}
+
+pub fn run(invocation: &Invocation, data_path: PathBuf) -> Result<String, String> {
+    let args = &invocation.args;
+    if matches!(invocation.command.as_str(), "help" | "--help" | "-h") {
+        return Ok(String::from(USAGE));
+    }
+    check_args(&invocation.command, args)?;
+
+    // Listing and exporting only read, so they leave a damaged file alone rather than quarantining it
+    let read_only = matches!(invocation.command.as_str(), "list" | "export");
+    let (mut portfolio, report) = if read_only { Portfolio::open_read_only(data_path) } else { Portfolio::open(data_path) };
+    if let Some(path) = &report.quarantined {
+        eprintln!("The data file had problems, a copy is at {}", path.display());
+    }
+    if let Some(error) = report.problems.iter().find(|error| error.is_fatal()) {
+        return Err(error.to_string());
+    }
+    let mut output = String::new();
+    for error in &report.problems {
+        eprintln!("Warning: {}", error);
+    }
+
+    match invocation.command.as_str() {
+        "list" => {
+            output = render_list(&portfolio, invocation.json);
+        },
+        "add" => {
+            let mut entry = Entry::default();
+            entry.set_name(args[0].clone());
+            apply_assignments(&portfolio, &mut entry, &args[1..])?;
+            entry.start_status(time::now_seconds());
+
+            let id = portfolio.add(entry);
+            output = format!("{}\n", id);
+        },
+        "set" => {
+            let id = parse_id(args.first())?;
+            let mut entry = find(&portfolio, id)?.clone();
+            apply_assignments(&portfolio, &mut entry, &args[1..])?;
+            portfolio.update(id, "set", |existing| *existing = entry);
+        },
+        "remove" => {
+            let id = parse_id(args.first())?;
+            find(&portfolio, id)?;
+            portfolio.remove(id);
+        },
+        "export" => {
+            let text = spreadsheet::export_csv(&sorted(&portfolio), portfolio.get_roommates());
+            match args.first() {
+                Some(path) => {
+                    backups::atomic_write(&PathBuf::from(path), text.as_bytes()).map_err(|error| format!("Failed to write {}: {}", path, error))?;
+                },
+                None => { output = text; },
+            }
+        },
+        "recalc" => {
+            portfolio.calculate();
+            portfolio.rewrite();
+            output = render_list(&portfolio, invocation.json);
+        },
+        "migrate" => {
+            let target = PathBuf::from(&args[0]);
+            if target.exists() { return Err(format!("{} already exists", target.display())); }
+            output = format!("Copied {} listing(s) to {}\n", portfolio.entries().len(), target.display());
+            portfolio.set_path(target);
+        },
+        _ => { unreachable!("check_args only lets known commands through"); },
+    }
+
+    let mut failures: Vec<String> = Vec::new();
+    if portfolio.is_dirty() {
+        for problem in portfolio.save() {
+            if problem.is_fatal() {
+                failures.push(problem.to_string());
+            }
+            else {
+                eprintln!("Warning: {}", problem);
+            }
+        }
+    }
+    if !failures.is_empty() {
+        return Err(failures.join("\n"));
+    }
+
+    Ok(output)
+}
+
+// Runs before the data file is opened, so a mistyped command can't set it aside
+fn check_args(command: &str, args: &[String]) -> Result<(), String> {
+    let problem = match command {
+        "list" | "recalc" => { (!args.is_empty()).then(|| format!("{} doesn't take arguments", command)) },
+        "add" => { args.is_empty().then(|| String::from("add needs a name")) },
+        "set" => {
+            parse_id(args.first())?;
+            (args.len() < 2).then(|| String::from("set needs at least one field=value"))
+        },
+        "remove" => {
+            parse_id(args.first())?;
+            (args.len() > 1).then(|| String::from("remove takes a single listing ID"))
+        },
+        "export" => { (args.len() > 1).then(|| String::from("export takes at most one file")) },
+        "migrate" => { (args.len() != 1).then(|| String::from("migrate needs the file to copy to")) },
+        other => { Some(format!("Unknown command \"{}\"\n\n{}", other, USAGE)) },
+    };
+    problem.map_or(Ok(()), Err)
+}
+
+fn parse_id(arg: Option<&String>) -> Result<u64, String> {
+    let Some(arg) = arg else { return Err(String::from("Missing the listing ID, see the list command")); };
+    arg.trim_start_matches('#').parse::<u64>().map_err(|_| format!("\"{}\" is not a listing ID", arg))
+}
+
+fn find(portfolio: &Portfolio, id: u64) -> Result<&Entry, String> {
+    portfolio.get(id).ok_or(format!("There is no listing with ID {}", id))
+}
+
+fn apply_assignments(portfolio: &Portfolio, entry: &mut Entry, assignments: &[String]) -> Result<(), String> {
+    for assignment in assignments {
+        let Some((name, value)) = assignment.split_once('=') else {
+            return Err(format!("Expected field=value, got \"{}\"", assignment));
+        };
+        let field = spreadsheet::input_field(name).ok_or(format!("\"{}\" is not a field that can be set", name))?;
+        spreadsheet::apply_cell(entry, field, value, &portfolio.get_money_format()).map_err(|problem| format!("{}: {}", field.label(), problem))?;
+    }
+    Ok(())
+}
+
+fn sorted(portfolio: &Portfolio) -> Vec<Entry> {
+    let mut list = portfolio.entries().to_vec();
+    list.sort_by_key(|entry| entry.get_id());
+    list
+}
+
+fn render_list(portfolio: &Portfolio, json: bool) -> String {
+    let list = sorted(portfolio);
+    let roommates = portfolio.get_roommates();
+    let money_format = portfolio.get_money_format();
+    if json {
+        let mut listings = JsonValue::new_array();
+        for entry in &list {
+            let Ok(mut listing) = store::save(entry) else { continue; };
+            listing[ID_KEY] = JsonValue::from(entry.get_id());
+            for field in COMPUTED {
+                listing[field.key().as_str()] = JsonValue::from(entry.get_money(field).unwrap_or_default().as_f64());
+            }
+            let splits: Vec<f64> = (0..roommates.len()).map(|index| entry.get_money(Fields::Split(index)).unwrap_or_default().as_f64()).collect();
+            listing[SPLITS_KEY] = JsonValue::from(splits);
+            let _ = listings.push(listing);
+        }
+        return format!("{}\n", listings.pretty(2));
+    }
+
+    let mut headers = vec![String::from("ID"), Fields::Name.label()];
+    headers.extend([Fields::MonthlyRent, Fields::TotalRent, Fields::MoveInTotal, Fields::LeaseCost].iter().map(|field| field.label()));
+    headers.extend(roommates.iter().enumerate().map(|(index, roommate)| roommate.display_name(index)));
+
+    let rows: Vec<Vec<String>> = list.iter().map(|entry| {
+        let mut row = vec![entry.get_id().to_string(), entry.get_name()];
+        for field in [Fields::MonthlyRent, Fields::TotalRent, Fields::MoveInTotal, Fields::LeaseCost] {
+            row.push(money_format.format(entry.get_money(field).unwrap_or_default()));
+        }
+        for index in 0..roommates.len() {
+            row.push(money_format.format(entry.get_money(Fields::Split(index)).unwrap_or_default()));
+        }
+        row
+    }).collect();
+
+    render_table(&headers, &rows)
+}
+
+// Text left aligned and everything else right aligned, so amounts line up
+pub fn render_table(headers: &[String], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|header| header.chars().count()).collect();
+    for row in rows {
+        for (column, cell) in row.iter().enumerate() {
+            widths[column] = widths[column].max(cell.chars().count());
+        }
+    }
+
+    let line = |cells: &[String]| -> String {
+        let padded: Vec<String> = cells.iter().enumerate().map(|(column, cell)| {
+            let padding = " ".repeat(widths[column] - cell.chars().count());
+            if column == 1 { format!("{}{}", cell, padding) } else { format!("{}{}", padding, cell) }
+        }).collect();
+        format!("{}\n", padded.join("  ").trim_end())
+    };
+
+    let mut text = line(headers);
+    let rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+    text.push_str(&format!("{}\n", rule.join("  ")));
+    for row in rows {
+        text.push_str(&line(row));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, read_dir, remove_dir_all, write};
+    use cost_analysis::test_support::workspace;
+
+    fn invocation(args: &[&str]) -> Option<Invocation> {
+        parse_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>()).unwrap()
+    }
+
+    #[test]
+    fn data_path_and_flags_are_not_commands() {
+        assert!(invocation(&["--data", "city.json"]).is_none());
+
+        let parsed = invocation(&["--data=city.json", "set", "3", "--json", "beds=2"]).unwrap();
+        assert_eq!(parsed.command, "set");
+        assert_eq!(parsed.args, ["3", "beds=2"]);
+        assert!(parsed.json);
+
+        assert!(parse_args(&[String::from("list"), String::from(paths::DATA_ARG)]).is_err());
+    }
+
+    #[test]
+    fn tables_align_amounts() {
+        let headers = vec![String::from("ID"), String::from("Name"), String::from("Rent")];
+        let rows = vec![vec![String::from("12"), String::from("Oak"), String::from("$900.00")]];
+
+        assert_eq!(render_table(&headers, &rows), "ID  Name     Rent\n--  ----  -------\n12  Oak   $900.00\n");
+    }
+
+    #[test]
+    fn commands_edit_the_data_file() {
+        let dir = workspace("cli");
+        let data_path = dir.join("rentdata.json");
+        let run_args = |args: &[&str]| run(&invocation(args).unwrap(), data_path.clone());
+
+        assert_eq!(run_args(&["add", "Maple Court", "Monthly Rent=$1,200", "beds=2"]), Ok(String::from("1\n")));
+        assert_eq!(run_args(&["add", "Maple Court"]), Ok(String::from("2\n")));
+        run_args(&["set", "2", "monthlyrent=950.50"]).unwrap();
+        run_args(&["remove", "1"]).unwrap();
+        assert!(run_args(&["set", "1", "beds=3"]).is_err());
+        assert!(run_args(&["set", "2", "beds=lots"]).is_err());
+
+        let listed = jzon::parse(&run_args(&["list", "--json"]).unwrap()).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0][ID_KEY].as_u64(), Some(2));
+        assert_eq!(listed[0]["monthlyrent"].as_f64(), Some(950.5));
+        assert_eq!(listed[0][SPLITS_KEY][0].as_f64(), Some(475.25));
+
+        let _ = remove_dir_all(dir);
+    }
+
+    #[test]
+    fn reading_leaves_a_damaged_file_alone() {
+        let dir = workspace("cli_damaged");
+        let data_path = dir.join("rentdata.json");
+        let _ = remove_dir_all(&dir);
+        create_dir_all(&dir).unwrap();
+        write(&data_path, "{ not json").unwrap();
+
+        assert!(run(&invocation(&["list"]).unwrap(), data_path.clone()).is_err());
+        assert!(run(&invocation(&["export"]).unwrap(), data_path.clone()).is_err());
+        // Mistakes on the command line are caught before the file is opened
+        assert!(run(&invocation(&["lsit"]).unwrap(), data_path.clone()).is_err());
+        assert!(run(&invocation(&["recalc", "now"]).unwrap(), data_path.clone()).is_err());
+        assert!(run(&invocation(&["remove", "oak"]).unwrap(), data_path.clone()).is_err());
+        assert!(data_path.exists());
+        assert_eq!(read_dir(&dir).unwrap().count(), 1);
+
+        let _ = remove_dir_all(dir);
+    }
+
+    #[test]
+    fn migrate_copies_into_sqlite() {
+        let dir = workspace("migrate");
+        let data_path = dir.join("rentdata.json");
+        let database = dir.join("rentdata.sqlite");
+        let run_args = |path: &PathBuf, args: &[&str]| run(&invocation(args).unwrap(), path.clone());
+
+        run_args(&data_path, &["add", "Maple Court", "monthlyrent=1000"]).unwrap();
+        run_args(&data_path, &["add", "Elm Street"]).unwrap();
+        assert_eq!(run_args(&data_path, &["migrate", database.to_str().unwrap()]), Ok(format!("Copied 2 listing(s) to {}\n", database.display())));
+        assert!(run_args(&data_path, &["migrate", database.to_str().unwrap()]).is_err());
+
+        run_args(&database, &["remove", "2"]).unwrap();
+        assert_eq!(run_args(&database, &["add", "Oak"]), Ok(String::from("3\n")));
+        let listed = jzon::parse(&run_args(&database, &["list", "--json"]).unwrap()).unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0][SPLITS_KEY][1].as_f64(), Some(500.0));
+
+        // Recalculating rewrites every row, not only the ones edited in this run
+        let connection = rusqlite::Connection::open(&database).unwrap();
+        connection.execute("UPDATE listings SET name = 'Stale'", []).unwrap();
+        run_args(&database, &["recalc"]).unwrap();
+        let stale: i64 = connection.query_row("SELECT COUNT(*) FROM listings WHERE name = 'Stale'", [], |row| row.get(0)).unwrap();
+        assert_eq!(stale, 0);
+
+        let _ = remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,81 @@
+// Just enough of RFC 4180 for spreadsheets: quoted cells, doubled quotes, and CRLF or LF line ends
+
+pub fn escape(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) || cell.starts_with(' ') || cell.ends_with(' ') {
+        return format!("\"{}\"", cell.replace('"', "\"\""));
+    }
+    cell.to_string()
+}
+
+pub fn write_row(cells: &[String]) -> String {
+    let escaped: Vec<String> = cells.iter().map(|cell| escape(cell)).collect();
+    format!("{}\r\n", escaped.join(","))
+}
+
+pub fn parse(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let text = text.strip_prefix('\u{FEFF}').unwrap_or(text);
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut row: Vec<String> = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => { chars.next(); cell.push('"'); },
+                '"' => { quoted = false; },
+                '\n' => { line += 1; cell.push(c); },
+                _ => { cell.push(c); },
+            }
+            continue;
+        }
+
+        match c {
+            '"' if cell.is_empty() => { quoted = true; },
+            '"' => { return Err(format!("Unexpected quote on line {}", line)); },
+            ',' => { row.push(std::mem::take(&mut cell)); },
+            '\r' if chars.peek() == Some(&'\n') => {},
+            '\n' | '\r' => {
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row));
+                line += 1;
+            },
+            _ => { cell.push(c); },
+        }
+    }
+
+    if quoted { return Err(format!("A quoted cell is never closed, starting before line {}", line)); }
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell);
+        rows.push(row);
+    }
+
+    // Blank lines, often left at the end by spreadsheets
+    rows.retain(|row| row.iter().any(|cell| !cell.trim().is_empty()));
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_awkward_cells() {
+        let cells = vec![String::from("Maple, Unit 4"), String::from("says \"hi\""), String::from("line\nbreak"), String::from("1200.50")];
+        let text = write_row(&cells);
+
+        assert_eq!(text, "\"Maple, Unit 4\",\"says \"\"hi\"\"\",\"line\nbreak\",1200.50\r\n");
+        assert_eq!(parse(&text), Ok(vec![cells]));
+    }
+
+    #[test]
+    fn parses_spreadsheet_exports() {
+        let rows = parse("\u{FEFF}Name,Rent\r\nOak,900\n\nPine,\n").unwrap();
+
+        assert_eq!(rows, vec![vec!["Name", "Rent"], vec!["Oak", "900"], vec!["Pine", ""]]);
+        assert!(parse("Name\n\"Oak").is_err());
+        assert!(parse("Na\"me").is_err());
+    }
+}
@@ -0,0 +1,199 @@
+use crate::objects::entry::Entry;
+
+const MAX_HISTORY: usize = 100;
+
+pub enum Command {
+    Add { index: usize, entry: Entry },
+    Remove { index: usize, entry: Entry },
+    Edit { what: String, before: Box<Entry>, after: Box<Entry> },
+    Batch { what: String, commands: Vec<Command> },
+}
+
+fn position(list: &[Entry], id: u64) -> Option<usize> {
+    list.iter().position(|entry| entry.is(id))
+}
+
+fn replace(list: &mut [Entry], replacement: &Entry) {
+    if let Some(index) = position(list, replacement.get_id()) {
+        list[index] = replacement.clone();
+    }
+}
+
+impl Command {
+    pub fn label(&self) -> String {
+        match self {
+            Command::Add { entry, .. } => { format!("add {}", entry.get_name()) },
+            Command::Remove { entry, .. } => { format!("delete {}", entry.get_name()) },
+            Command::Edit { what, before, after } if before.get_name() != after.get_name() => {
+                format!("{} {} to {}", what, before.get_name(), after.get_name())
+            },
+            Command::Edit { what, after, .. } => { format!("{} on {}", what, after.get_name()) },
+            Command::Batch { what, .. } => { what.clone() },
+        }
+    }
+
+    fn undo(&self, list: &mut Vec<Entry>) {
+        match self {
+            Command::Add { entry, .. } => {
+                if let Some(index) = position(list, entry.get_id()) { list.remove(index); }
+            },
+            Command::Remove { index, entry } => { list.insert((*index).min(list.len()), entry.clone()); },
+            Command::Edit { before, .. } => { replace(list, before); },
+            Command::Batch { commands, .. } => {
+                for command in commands.iter().rev() { command.undo(list); }
+            },
+        }
+    }
+
+    fn redo(&self, list: &mut Vec<Entry>) {
+        match self {
+            Command::Add { index, entry } => { list.insert((*index).min(list.len()), entry.clone()); },
+            Command::Remove { entry, .. } => {
+                if let Some(index) = position(list, entry.get_id()) { list.remove(index); }
+            },
+            Command::Edit { after, .. } => { replace(list, after); },
+            Command::Batch { commands, .. } => {
+                for command in commands { command.redo(list); }
+            },
+        }
+    }
+
+    // Cells commit on every keystroke, so back to back edits of the same thing make one step
+    fn absorb(&mut self, next: &Command) -> bool {
+        match (self, next) {
+            (Command::Edit { what, after, .. }, Command::Edit { what: next_what, before: next_before, after: next_after })
+                if what == next_what && after.is(next_before.get_id()) => {
+                after.clone_from(next_after);
+                true
+            },
+            _ => false,
+        }
+    }
+}
+
+// A recorded command with a number that is never reused, so a toast can tell whether its command is next to undo
+struct Step {
+    sequence: u64,
+    command: Command,
+}
+
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+    next_sequence: u64,
+}
+
+impl History {
+    pub fn record(&mut self, command: Command) {
+        self.redo.clear();
+        if self.undo.last_mut().is_some_and(|last| last.command.absorb(&command)) { return; }
+
+        self.undo.push(Step { sequence: self.next_sequence, command });
+        self.next_sequence += 1;
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.remove(0);
+        }
+    }
+
+    // Returns what was undone, for telling the user
+    pub fn undo(&mut self, list: &mut Vec<Entry>) -> Option<String> {
+        let step = self.undo.pop()?;
+        step.command.undo(list);
+        let label = step.command.label();
+        self.redo.push(step);
+        Some(label)
+    }
+
+    pub fn redo(&mut self, list: &mut Vec<Entry>) -> Option<String> {
+        let step = self.redo.pop()?;
+        step.command.redo(list);
+        let label = step.command.label();
+        self.undo.push(step);
+        Some(label)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn next_undo(&self) -> Option<u64> {
+        self.undo.last().map(|step| step.sequence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::fields::Fields;
+    use crate::objects::money::Money;
+    use crate::test_support::{named, with_rent};
+
+    fn ids(list: &[Entry]) -> Vec<u64> {
+        list.iter().map(|entry| entry.get_id()).collect()
+    }
+
+    #[test]
+    fn undoes_and_redoes_adds_and_deletes() {
+        let mut list = vec![named(1, "Oak"), named(2, "Oak")];
+        let mut history = History::default();
+
+        let removed = list.remove(0);
+        history.record(Command::Remove { index: 0, entry: removed });
+        list.push(named(3, "Elm"));
+        history.record(Command::Add { index: 1, entry: named(3, "Elm") });
+
+        assert_eq!(history.undo(&mut list), Some(String::from("add Elm")));
+        assert_eq!(history.undo(&mut list), Some(String::from("delete Oak")));
+        assert_eq!(ids(&list), [1, 2]);
+        assert!(!history.can_undo());
+
+        history.redo(&mut list);
+        assert_eq!(ids(&list), [2]);
+        assert!(history.can_redo());
+    }
+
+    #[test]
+    fn keystrokes_in_one_cell_fold_into_one_step() {
+        let mut list = vec![with_rent("Oak", 9)];
+        let mut history = History::default();
+
+        for cents in [90, 900, 9000] {
+            let before = list[0].clone();
+            list[0] = with_rent("Oak", cents);
+            history.record(Command::Edit { what: Fields::MonthlyRent.label(), before: Box::new(before), after: Box::new(list[0].clone()) });
+        }
+        let before = list[0].clone();
+        list[0] = with_rent("Oaks", 9000);
+        history.record(Command::Edit { what: String::from("rename"), before: Box::new(before), after: Box::new(list[0].clone()) });
+
+        assert_eq!(history.undo(&mut list), Some(String::from("rename Oak to Oaks")));
+        assert_eq!(history.undo(&mut list), Some(String::from("Monthly Rent on Oak")));
+        assert_eq!(list[0].get_money(Fields::MonthlyRent), Some(Money::from_cents(9)));
+
+        history.record(Command::Edit { what: String::from("link"), before: Box::new(list[0].clone()), after: Box::new(list[0].clone()) });
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn steps_stay_distinct_once_the_history_is_full() {
+        let mut list: Vec<Entry> = Vec::new();
+        let mut history = History::default();
+        for id in 0..MAX_HISTORY as u64 {
+            history.record(Command::Add { index: 0, entry: named(id, "Oak") });
+        }
+        let full = history.next_undo();
+
+        history.record(Command::Remove { index: 0, entry: named(500, "Elm") });
+        assert_ne!(history.next_undo(), full);
+
+        let deleted = history.next_undo();
+        history.undo(&mut list);
+        history.redo(&mut list);
+        assert_eq!(history.next_undo(), deleted);
+    }
+}
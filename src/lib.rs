@@ -0,0 +1,15 @@
+pub mod backups;
+pub mod csv;
+pub mod history;
+pub mod load_error;
+pub mod migrations;
+pub mod objects;
+pub mod paths;
+pub mod save_error;
+pub mod spreadsheet;
+pub mod storage;
+pub mod store;
+pub mod time;
+// The binary's tests use these too, and they can't see anything behind the library's cfg(test)
+#[doc(hidden)]
+pub mod test_support;
@@ -0,0 +1,49 @@
+use std::fmt::{Display, Formatter, Result};
+
+pub enum LoadError {
+    Unreadable(String),
+    Malformed(String),
+    NotAnObject,
+    InvalidEntry { entry: String },
+    MissingKey { entry: String, key: &'static str },
+    InvalidValue { entry: String, key: &'static str },
+    NewerVersion(i32),
+    QuarantineFailed(String),
+}
+
+impl LoadError {
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, LoadError::Unreadable(_) | LoadError::Malformed(_) | LoadError::NotAnObject)
+    }
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            LoadError::Unreadable(error) => { write!(f, "The data file could not be read: {}", error) },
+            LoadError::Malformed(error) => { write!(f, "The data file is not valid JSON: {}", error) },
+            LoadError::NotAnObject => { write!(f, "The data file does not contain a JSON object") },
+            LoadError::InvalidEntry { entry } => { write!(f, "\"{}\" is not a listing object", entry) },
+            LoadError::MissingKey { entry, key } => { write!(f, "\"{}\" is missing \"{}\"", entry, key) },
+            LoadError::InvalidValue { entry, key } => { write!(f, "\"{}\" has an invalid \"{}\"", entry, key) },
+            LoadError::NewerVersion(version) => { write!(f, "The data file was saved by a newer version (format {}), anything this version doesn't know about is dropped on save", version) },
+            LoadError::QuarantineFailed(error) => { write!(f, "The original file could not be set aside: {}", error) },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_problems_name_the_entry_and_key() {
+        let missing = LoadError::MissingKey { entry: String::from("Maple Court"), key: "monthlyrent" };
+        let invalid = LoadError::InvalidValue { entry: String::from("Maple Court"), key: "beds" };
+
+        assert_eq!(missing.to_string(), "\"Maple Court\" is missing \"monthlyrent\"");
+        assert_eq!(invalid.to_string(), "\"Maple Court\" has an invalid \"beds\"");
+        assert!(!missing.is_fatal());
+        assert!(LoadError::NotAnObject.is_fatal());
+    }
+}
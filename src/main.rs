@@ -1,30 +1,53 @@
-use std::{env, fs::{read_to_string, write, File}, io::Write};
-
+use std::{collections::HashMap, env, hash::Hash, fs::read_to_string, path::PathBuf, time::Instant};
+
+use cost_analysis::load_error::LoadError;
+use cost_analysis::objects::concession::{Concession, ConcessionKind};
+use cost_analysis::objects::entry::Entry;
+use cost_analysis::objects::fields::Fields;
+use cost_analysis::objects::filter::Filters;
+use cost_analysis::objects::format::{parse_count, parse_percent, Currency, Locale};
+use cost_analysis::objects::line_item::LineItem;
+use cost_analysis::objects::money::Money;
+use cost_analysis::objects::roommate::Roommate;
+use cost_analysis::objects::sort::SortOrder;
+use cost_analysis::objects::split::SplitPolicy;
+use cost_analysis::objects::status::Status;
+use cost_analysis::spreadsheet::{self, Duplicates, ImportWizard, RowPlan};
+use cost_analysis::store::{LoadReport, Portfolio};
+use cost_analysis::{backups, csv, migrations, paths, time};
 use eframe::{egui, NativeOptions};
 use egui::Ui;
 use egui_extras::{Column, TableBuilder};
-use jzon::JsonValue;
-
-mod objects;
 
-use crate::objects::entry::Entry;
-use crate::objects::fields::Fields;
+mod cli;
+mod notifications;
 
-const BED_KEY: &str = "beds";
-const BATH_KEY: &str = "baths";
-const DEPOSIT_KEY: &str = "deposit";
-const PET_DEPOSIT_KEY: &str = "petdeposit";
-const PET_MONTHLY_KEY: &str = "petmonthly";
-const PARKING_MONTHLY_KEY: &str = "parkingmonthly";
-const MONTHLY_RENT_KEY: &str = "monthlyrent";
-const LINK_KEY: &str = "link";
-const RENT_DATA_KEY: &str = "rentdata";
-const PROPERTIES_KEY: &str = "properties";
+use crate::notifications::{Level, Notifications, TOAST_DURATION};
 
-const PET_COUNT_KEY: &str = "petcount";
 const ZOOM: f32 = 1.5;
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let parsed = paths::resolve_data_path(&args).and_then(|data_path| Ok((data_path, cli::parse_args(&args)?)));
+    let (data_path, invocation) = match parsed {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            eprintln!("Error: {}\n\n{}", error, cli::USAGE);
+            std::process::exit(1);
+        },
+    };
+
+    if let Some(invocation) = invocation {
+        match cli::run(&invocation, data_path) {
+            Ok(output) => { print!("{}", output); },
+            Err(error) => {
+                eprintln!("Error: {}", error);
+                std::process::exit(1);
+            },
+        }
+        return;
+    }
+
     let options = NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size([640.0,480.0]),
         ..Default::default()
@@ -36,7 +59,9 @@ fn main() {
             // This gives us image support:
             egui_extras::install_image_loaders(&cc.egui_ctx);
 
-            Ok(Box::<MyApp>::default())
+            let mut app = MyApp::new(data_path);
+            app.remember_workspace();
+            Ok(Box::new(app))
         }),
     );
     if result.is_err() {
@@ -44,472 +69,1128 @@ fn main() {
     }
 }
 
-fn fetch_i8(data: &JsonValue, key: &str) -> Option<i8> {
-    let data_key = data.get(key);
-    if data_key.is_none() { return None; }
+// Keeps the typed text until it parses, so a half typed or mistyped value isn't thrown away.
+// Gives the parsed text back once it changes, and a problem only once it loses focus without parsing
+fn build_draft<K: Copy + Eq + Hash, T>(drafts: &mut HashMap<K, String>, key: K, shown: String, width: f32, ui: &mut Ui, parse: impl Fn(&str) -> Result<T, String>) -> Option<Result<T, String>> {
+    let mut text = drafts.get(&key).cloned().unwrap_or(shown);
+    let problem = drafts.get(&key).and_then(|draft| parse(draft).err());
 
-    data_key.unwrap().as_i8()
-}
+    let mut edit = egui::TextEdit::singleline(&mut text).desired_width(width);
+    if problem.is_some() {
+        edit = edit.background_color(ui.visuals().error_fg_color.gamma_multiply(0.25));
+    }
+    let mut response = ui.add(edit);
+    if let Some(problem) = &problem {
+        response = response.on_hover_text(problem);
+    }
 
-fn fetch_f32(data: &JsonValue, key: &str) -> Option<f32> {
-    let data_key = data.get(key);
-    if data_key.is_none() { return None; }
+    if response.changed() {
+        drafts.insert(key, text.clone());
+    }
+    else if !response.lost_focus() {
+        return None;
+    }
 
-    data_key.unwrap().as_f32()
+    match parse(&text) {
+        Ok(value) => {
+            if response.lost_focus() {
+                drafts.remove(&key);
+            }
+            Some(Ok(value))
+        },
+        Err(problem) => response.lost_focus().then_some(Err(problem)),
+    }
 }
 
-fn build(name: &str, data: &JsonValue) -> Option<Entry> {
-    let beds = fetch_i8(data, BED_KEY);
-    if beds.is_none() { return None; }
+fn level_text(ui: &Ui, level: Level, message: &str, repeats: u32) -> egui::RichText {
+    let text = if repeats > 1 { format!("{}: {} (×{})", level.label(), message, repeats) } else { format!("{}: {}", level.label(), message) };
+    match level {
+        Level::Info => { egui::RichText::new(text) },
+        Level::Warning => { egui::RichText::new(text).color(ui.visuals().warn_fg_color) },
+        Level::Error => { egui::RichText::new(text).color(ui.visuals().error_fg_color) },
+    }
+}
 
-    let baths = fetch_i8(data, BATH_KEY);
-    if baths.is_none()  { return None; }
+struct MyApp {
+    portfolio: Portfolio,
+    sortorder: SortOrder,
+    details: Option<u64>,
+    expanded: Vec<u64>,
+    recent: Vec<PathBuf>,
+    path_prompt: Option<PathPrompt>,
+    backups_open: bool,
+    load_errors: Vec<LoadError>,
+    quarantined: Option<PathBuf>,
+    notifications: Notifications,
+    log_open: bool,
+    drafts: HashMap<(u64, Cell), String>,
+    range_drafts: HashMap<(Fields, bool), String>,
+    import: Option<ImportWizard>,
+}
 
-    let deposit = fetch_f32(data, DEPOSIT_KEY);
-    if deposit.is_none()  { return None; }
+// Where typed text is kept until it parses: a table field, or an amount inside one of a listing's lists
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Cell {
+    Field(Fields),
+    LineItem(usize),
+    Room(usize),
+    Concession(usize),
+}
 
-    let pet_deposit = fetch_f32(data, PET_DEPOSIT_KEY);
-    if pet_deposit.is_none()  { return None; }
+#[derive(Clone, Copy, PartialEq)]
+enum PathAction {
+    Open,
+    SaveAs,
+    ImportCsv,
+    ExportCsv,
+}
 
-    let pet_monthly= fetch_f32(data, PET_MONTHLY_KEY);
-    if pet_monthly.is_none()  { return None; }
+struct PathPrompt {
+    action: PathAction,
+    text: String,
+}
 
-    let parking_monthly= fetch_f32(data, PARKING_MONTHLY_KEY);
-    if parking_monthly.is_none()  { return None; }
+impl MyApp {
+    fn new(data_path: PathBuf) -> Self {
+        let (portfolio, report) = Portfolio::open(data_path);
+        let mut obj = Self {
+            portfolio,
+            sortorder: SortOrder::default(),
+            details: None,
+            expanded: Vec::new(),
+            recent: paths::load_recent(),
+            path_prompt: None,
+            backups_open: false,
+            load_errors: Vec::new(),
+            quarantined: None,
+            notifications: Notifications::default(),
+            log_open: false,
+            drafts: HashMap::new(),
+            range_drafts: HashMap::new(),
+            import: None,
+        };
 
-    let monthly_rent= fetch_f32(data, MONTHLY_RENT_KEY);
-    if monthly_rent.is_none()  { return None; }
+        obj.report_load(report);
 
-    let link = data.get(LINK_KEY);
-    if link.is_none() { return None; }
+        obj
+    }
 
-    Some(Entry::new(
-        String::from(name), 
-        data.get(BED_KEY).unwrap().as_i8().unwrap(), 
-        data.get(BATH_KEY).unwrap().as_i8().unwrap(), 
-        data.get(DEPOSIT_KEY).unwrap().as_f32().unwrap(), 
-        data.get(PET_DEPOSIT_KEY).unwrap().as_f32().unwrap(), 
-        data.get(PET_MONTHLY_KEY).unwrap().as_f32().unwrap(), 
-        data.get(PARKING_MONTHLY_KEY).unwrap().as_f32().unwrap(), 
-        data.get(MONTHLY_RENT_KEY).unwrap().as_f32().unwrap(), 
-        data.get(LINK_KEY).unwrap().to_string()))
-}
+    fn open_workspace(&mut self, path: PathBuf) {
+        if self.portfolio.is_dirty() { self.save(); }
+        let mut notifications = std::mem::take(&mut self.notifications);
+        notifications.forget_undo();
+        *self = MyApp::new(path);
+        notifications.absorb(std::mem::take(&mut self.notifications));
+        self.notifications = notifications;
+        self.remember_workspace();
+    }
 
-fn save(data: &Entry) -> Result<JsonValue, &str> {
-    let mut entry_value = JsonValue::new_object();
+    fn save_workspace_as(&mut self, path: PathBuf) {
+        self.portfolio.set_path(path);
+        self.save();
+        self.remember_workspace();
+    }
 
-    if entry_value.insert(BED_KEY, data.get_i8(Fields::Beds)).is_err() { return Err("Failed to save bed count!"); }
-    if entry_value.insert(BATH_KEY, data.get_i8(Fields::Baths)).is_err() { return Err("Failed to save baths count!"); }
-    if entry_value.insert(DEPOSIT_KEY, data.get_f32(Fields::Deposit)).is_err() { return Err("Failed to save deposit!"); }
-    if entry_value.insert(PET_DEPOSIT_KEY, data.get_f32(Fields::PetDeposit)).is_err() { return Err("Failed to save pet deposit!"); }
-    if entry_value.insert(PET_MONTHLY_KEY, data.get_f32(Fields::PetMonthly)).is_err() { return Err("Failed to save pet monthly!"); }
-    if entry_value.insert(PARKING_MONTHLY_KEY, data.get_f32(Fields::ParkingMonthly)).is_err() { return Err("Failed to save parking monthly!"); }
-    if entry_value.insert(MONTHLY_RENT_KEY, data.get_f32(Fields::MonthlyRent)).is_err() { return Err("Failed to save monthly rent!"); }
-    if entry_value.insert(LINK_KEY, data.get_link()).is_err() { return Err("Failed to save the link!"); }
+    fn remember_workspace(&mut self) {
+        if let Err(error) = paths::remember_recent(&mut self.recent, self.portfolio.get_path()) {
+            self.notifications.warn(format!("Failed to save recent workspaces: {}", error));
+        }
+    }
 
-    Ok(entry_value)
-}
+    fn show_workspace_bar(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label(format!("Workspace: {}", self.portfolio.get_path().display()));
+            if ui.button("Open…").clicked() {
+                self.path_prompt = Some(PathPrompt { action: PathAction::Open, text: String::new() });
+            }
+            if ui.button("Save As…").clicked() {
+                self.path_prompt = Some(PathPrompt { action: PathAction::SaveAs, text: self.portfolio.get_path().to_string_lossy().to_string() });
+            }
+            if ui.button("Import CSV…").clicked() {
+                self.path_prompt = Some(PathPrompt { action: PathAction::ImportCsv, text: String::new() });
+            }
+            if ui.button("Export CSV…").clicked() {
+                self.path_prompt = Some(PathPrompt { action: PathAction::ExportCsv, text: self.portfolio.get_path().with_extension("csv").to_string_lossy().to_string() });
+            }
+            if ui.button("Backups…").clicked() {
+                self.backups_open = true;
+            }
+            if ui.add_enabled(self.portfolio.can_undo(), egui::Button::new("Undo")).clicked() {
+                self.undo();
+            }
+            if ui.add_enabled(self.portfolio.can_redo(), egui::Button::new("Redo")).clicked() {
+                self.redo();
+            }
+            let errors = self.notifications.count(Level::Error);
+            let label = if errors > 0 { format!("Log ({} errors)", errors) } else { String::from("Log") };
+            if ui.button(label).clicked() {
+                self.log_open = !self.log_open;
+            }
+        });
+    }
 
-struct MyApp {
-    sortorder: Fields,
-    list: Vec<Entry>,
-    write_flag: bool,
-    read_flag: bool,
-    pet_count : i8,
-}
+    fn show_path_prompt(&mut self, ctx: &egui::Context) {
+        let Some(prompt) = self.path_prompt.as_mut() else { return; };
 
-impl Default for MyApp {
-    fn default() -> Self {
-        let mut obj = Self {
-            sortorder: Fields::Name,
-            list: Vec::new(),
-            write_flag: false,
-            read_flag: true,
-            pet_count: 2,
+        let title = match prompt.action {
+            PathAction::Open => "Open workspace",
+            PathAction::SaveAs => "Save workspace as",
+            PathAction::ImportCsv => "Import listings from CSV",
+            PathAction::ExportCsv => "Export listings to CSV",
         };
+        let mut open = true;
+        let mut chosen: Option<PathBuf> = None;
+
+        egui::Window::new(title)
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("File: ");
+                    ui.text_edit_singleline(&mut prompt.text);
+                    let label = match prompt.action {
+                        PathAction::Open => "Open",
+                        PathAction::SaveAs => "Save",
+                        PathAction::ImportCsv => "Read",
+                        PathAction::ExportCsv => "Export",
+                    };
+                    if ui.button(label).clicked() && !prompt.text.trim().is_empty() {
+                        chosen = Some(PathBuf::from(prompt.text.trim()));
+                    }
+                });
 
-        obj.read();
+                if prompt.action == PathAction::Open && !self.recent.is_empty() {
+                    ui.separator();
+                    ui.label("Recent workspaces");
+                    for path in &self.recent {
+                        if ui.button(path.to_string_lossy()).clicked() {
+                            chosen = Some(path.clone());
+                        }
+                    }
+                }
+            });
 
-        obj
+        let action = prompt.action;
+        if !open || chosen.is_some() {
+            self.path_prompt = None;
+        }
+        match (chosen, action) {
+            (Some(path), PathAction::Open) => { self.open_workspace(path); },
+            (Some(path), PathAction::SaveAs) => { self.save_workspace_as(path); },
+            (Some(path), PathAction::ImportCsv) => { self.start_import(path); },
+            (Some(path), PathAction::ExportCsv) => { self.export_csv(path); },
+            (None, _) => {},
+        }
     }
-}
 
-impl MyApp {
-    fn name_unique(&mut self, new_name: String) -> bool {
-        for entry in self.list.iter_mut() {
-            if entry.get_name().eq_ignore_ascii_case(&new_name) {
-                return false;
-            }
+    fn export_csv(&mut self, path: PathBuf) {
+        let text = spreadsheet::export_csv(self.portfolio.entries(), self.portfolio.get_roommates());
+        match backups::atomic_write(&path, text.as_bytes()) {
+            Ok(_) => { self.notifications.info(format!("Exported {} listing(s) to {}", self.portfolio.entries().len(), path.display())); },
+            Err(error) => { self.notifications.error(format!("Failed to export to {}: {}", path.display(), error)); },
         }
-        true
     }
 
-    fn remove(&mut self, name: String) {
-        let mut index: usize = 0;
-        loop {
-            if self.list[index].is(&name) {
-                self.list.remove(index);
-                self.write_flag = true;
-                break;
+    fn start_import(&mut self, path: PathBuf) {
+        let rows = match read_to_string(&path).map_err(|error| error.to_string()).and_then(|text| csv::parse(&text)) {
+            Ok(rows) => rows,
+            Err(error) => {
+                self.notifications.error(format!("Failed to read {}: {}", path.display(), error));
+                return;
             }
-            
-            index = index + 1;
-            self.write_flag = true;
-            if index >= self.list.len() { return; }
+        };
+        if rows.is_empty() {
+            self.notifications.warn(format!("{} has no rows to import", path.display()));
+            return;
         }
+        self.import = Some(ImportWizard::new(path.display().to_string(), rows));
     }
 
-    fn update_name(&mut self, old : String, new : String) {
-        for entry in self.list.iter_mut() {
-            if entry.is(&old) {
-                entry.set_name(new);
-                self.write_flag = true;
-                break;
-            }
-        } 
+    fn show_import_wizard(&mut self, ctx: &egui::Context) {
+        let Some(import) = self.import.as_mut() else { return; };
+
+        let money_format = self.portfolio.get_money_format();
+        let plan = import.plan(self.portfolio.entries(), &money_format);
+        let importable = plan.iter().filter(|row| matches!(row, RowPlan::New(_) | RowPlan::Update(_))).count();
+        let mut open = true;
+        let mut confirmed = false;
+        let mut cancelled = false;
+
+        egui::Window::new(format!("Import {}", import.source))
+            .id(egui::Id::new("import"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                if ui.checkbox(&mut import.has_header, "First row is a header").changed() && import.has_header {
+                    import.auto_map();
+                    import.has_header = true;
+                }
+
+                ui.label("Columns");
+                ui.label(format!("Only the table's own columns can be imported. {}", spreadsheet::NOT_IN_CSV));
+                egui::Grid::new("import_mapping").striped(true).show(ui, |ui| {
+                    for column in 0..import.mapping.len() {
+                        ui.label(import.column_name(column));
+                        let selected = import.mapping[column].map(|field| field.label()).unwrap_or(String::from("(ignore)"));
+                        egui::ComboBox::from_id_salt(("import_column", column))
+                            .selected_text(selected)
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut import.mapping[column], None, "(ignore)");
+                                for field in Fields::INPUTS {
+                                    ui.selectable_value(&mut import.mapping[column], Some(field), field.label());
+                                }
+                            });
+                        ui.end_row();
+                    }
+                });
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.label("Names already listed or repeated in the file: ");
+                    for duplicates in Duplicates::ALL {
+                        ui.radio_value(&mut import.duplicates, duplicates, duplicates.label());
+                    }
+                });
+                ui.separator();
+
+                ui.label(format!("Preview of the first {} rows", spreadsheet::PREVIEW_ROWS.min(plan.len())));
+                egui::Grid::new("import_preview").striped(true).show(ui, |ui| {
+                    for row in plan.iter().take(spreadsheet::PREVIEW_ROWS) {
+                        match row {
+                            RowPlan::New(entry) => {
+                                ui.label(entry.get_name());
+                                ui.label(format!("New, {} a month", money_format.format(entry.get_money(Fields::MonthlyRent).unwrap_or_default())));
+                            },
+                            RowPlan::Update(entry) => {
+                                ui.label(entry.get_name());
+                                ui.label(egui::RichText::new("Updates the existing listing").color(ui.visuals().warn_fg_color));
+                            },
+                            RowPlan::Skip(reason) => {
+                                ui.label("—");
+                                ui.label(egui::RichText::new(format!("Duplicate: {}", reason)).color(ui.visuals().warn_fg_color));
+                            },
+                            RowPlan::Invalid(problem) => {
+                                ui.label("—");
+                                ui.label(egui::RichText::new(problem).color(ui.visuals().error_fg_color));
+                            },
+                        }
+                        ui.end_row();
+                    }
+                });
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(importable > 0, egui::Button::new(format!("Import {} of {}", importable, plan.len()))).clicked() {
+                        confirmed = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancelled = true;
+                    }
+                });
+            });
+
+        if confirmed {
+            self.merge_import(plan);
+        }
+        if !open || confirmed || cancelled {
+            self.import = None;
+        }
     }
 
-    fn update_link(&mut self, name: String, link: String) {
-        for entry in self.list.iter_mut() {
-            if entry.is(&name) {
-                entry.set_link(link);
-                self.write_flag = true;
-                break;
-            }
-        } 
+    fn merge_import(&mut self, plan: Vec<RowPlan>) {
+        let count = self.portfolio.merge(plan);
+        self.notifications.info(format!("Imported {} listing(s)", count));
     }
 
-    fn update_i8(&mut self, name: String, field: Fields, new_value: i8) {
-        let mut index: usize = 0;
-        loop {
-            if self.list[index].is(&name) {
-                self.list[index].set_i8(field, new_value);
-                self.write_flag = true;
-                break;
-            }
-            index = index + 1;
-            if index >= self.list.len() { return; }
+    fn show_backups(&mut self, ctx: &egui::Context) {
+        if !self.backups_open { return; }
+
+        let mut open = true;
+        let mut restore: Option<PathBuf> = None;
+
+        egui::Window::new("Backups")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Backups kept: ");
+                    let mut backup_count = self.portfolio.get_backup_count();
+                    if ui.add(egui::DragValue::new(&mut backup_count).range(0..=50)).changed() {
+                        self.portfolio.set_backup_count(backup_count);
+                    }
+                });
+                ui.separator();
+
+                let backups = self.portfolio.list_backups();
+                if backups.is_empty() {
+                    ui.label("No backups yet.");
+                }
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    for backup in &backups {
+                        ui.horizontal(|ui| {
+                            ui.label(backup.label());
+                            if ui.button("Restore").clicked() {
+                                restore = Some(backup.path.clone());
+                            }
+                        });
+                    }
+                });
+            });
+
+        self.backups_open = open;
+        if let Some(backup) = restore {
+            self.restore_backup(backup);
         }
     }
 
-    fn update_f32(&mut self, name: String, field: Fields, new_value: f32) {
-        let mut index: usize = 0;
-        loop {
-            if self.list[index].is(&name) {
-                self.list[index].set_f32(field, new_value);
-                self.list[index].calculate(self.pet_count);
-                self.write_flag = true;
-                break;
-            }
-            index = index + 1;
-            if index >= self.list.len() { return; }
+    fn restore_backup(&mut self, backup: PathBuf) {
+        if self.portfolio.is_dirty() { self.save(); }
+
+        match self.portfolio.restore_backup(&backup) {
+            Ok(report) => {
+                self.drafts.clear();
+                self.range_drafts.clear();
+                self.report_load(report);
+                self.notifications.info(format!("Restored {}", backup.display()));
+            },
+            Err(error) => { self.notifications.error(error); },
         }
     }
 
-    fn show_error(&mut self, message: String) {
-        println!("Error: {}", message);
+    fn show_toasts(&mut self, ctx: &egui::Context) {
+        let now = Instant::now();
+        let mut dismissed: Option<usize> = None;
+        let mut undo = false;
+        let next_undo = self.portfolio.next_undo();
+        let toasts = self.notifications.get_toasts(now);
+        if toasts.is_empty() { return; }
+
+        egui::Area::new(egui::Id::new("toasts"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                for (index, notification) in &toasts {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(level_text(ui, notification.level, &notification.message, notification.repeats));
+                            if notification.undo_step.is_some() && notification.undo_step == next_undo && ui.button("Undo").clicked() {
+                                undo = true;
+                                dismissed = Some(*index);
+                            }
+                            if ui.small_button("×").clicked() {
+                                dismissed = Some(*index);
+                            }
+                        });
+                    });
+                }
+            });
+
+        let remaining = toasts.iter()
+            .map(|(_, notification)| TOAST_DURATION.saturating_sub(now.duration_since(notification.created)))
+            .min()
+            .unwrap_or(TOAST_DURATION);
+        ctx.request_repaint_after(remaining);
+
+        if let Some(index) = dismissed {
+            self.notifications.dismiss(index);
+        }
+        if undo {
+            self.undo();
+        }
     }
 
-    fn build_i8_field(&mut self, name: &String, field: Fields, entry: &Entry, ui: &mut Ui) {
-        let original_count = entry.get_i8(field).unwrap();
-        let mut value_string = original_count.to_string();
-        if original_count == 0 {
-            value_string = String::new();
+    fn show_log(&mut self, ctx: &egui::Context) {
+        if !self.log_open { return; }
+
+        let mut open = true;
+        let mut clear = false;
+
+        egui::Window::new("Log")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                let log = self.notifications.get_log();
+                if log.is_empty() {
+                    ui.label("Nothing to report.");
+                }
+                egui::ScrollArea::vertical().max_height(300.0).stick_to_bottom(true).show(ui, |ui| {
+                    for notification in log {
+                        ui.label(level_text(ui, notification.level, &notification.message, notification.repeats));
+                    }
+                });
+                ui.separator();
+                if ui.button("Clear").clicked() {
+                    clear = true;
+                }
+            });
+
+        self.log_open = open;
+        if clear {
+            self.notifications.clear();
         }
-        let response = ui.add(egui::TextEdit::singleline(&mut value_string));
+    }
 
-        if response.lost_focus() || response.changed() {
-            if value_string.is_empty() { 
-                self.update_i8(name.clone(), field, 0); 
-            } 
-            else {
-                match value_string.parse::<i8>() {
-                    Ok(value) => {
-                        if original_count != value {
-                            self.update_i8(name.clone(), field, value);
-                        }
-                    },
-                    Err(_) => {self.show_error(String::from("Invalid data for bed count"));},
+    fn show_load_errors(&mut self, ctx: &egui::Context) {
+        if self.load_errors.is_empty() { return; }
+
+        let mut open = true;
+        let mut dismissed = false;
+        let mut show_backups = false;
+
+        egui::Window::new("Problems loading data")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                if self.load_errors.iter().any(|error| error.is_fatal()) {
+                    ui.label("The data file could not be loaded, so the workspace starts empty.");
+                }
+                else {
+                    ui.label("Some of the data could not be loaded and was left out; everything else was kept.");
                 }
+                if let Some(path) = &self.quarantined {
+                    ui.label(format!("The original file was kept as {}", path.display()));
+                }
+                ui.separator();
+
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    for error in &self.load_errors {
+                        ui.label(error.to_string());
+                    }
+                });
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if ui.button("Restore from backup…").clicked() {
+                        show_backups = true;
+                    }
+                    if ui.button("Dismiss").clicked() {
+                        dismissed = true;
+                    }
+                });
+            });
+
+        if !open || dismissed || show_backups {
+            self.load_errors.clear();
+        }
+        if show_backups {
+            self.backups_open = true;
+        }
+    }
+
+    fn remove(&mut self, id: u64) {
+        if let Some(entry) = self.portfolio.remove(id) {
+            if let Some(step) = self.portfolio.next_undo() {
+                self.notifications.undoable(format!("Deleted {}", entry.get_name()), step);
             }
         }
+        if self.details == Some(id) {
+            self.details = None;
+        }
+        self.expanded.retain(|expanded| *expanded != id);
+        self.forget_drafts(id, |_| true);
     }
 
-    fn build_f32_field(&mut self, name: &String, field: Fields, entry: &Entry, ui: &mut Ui) {
-        let original_count = entry.get_f32(field).unwrap();
-        let mut value_string = original_count.to_string();
-        if original_count == 0.0 {
-            value_string = String::new();
+    fn is_expanded(&self, id: u64) -> bool {
+        self.expanded.contains(&id)
+    }
+
+    fn toggle_expanded(&mut self, id: u64) {
+        if self.is_expanded(id) {
+            self.expanded.retain(|expanded| *expanded != id);
         }
-        let response = ui.add(egui::TextEdit::singleline(&mut value_string));
+        else {
+            self.expanded.push(id);
+        }
+    }
 
-        if response.lost_focus() || response.changed() {
-            if value_string.is_empty() {
-                self.update_f32(name.clone(), field, 0.0);
-            }
-            else {
-                match value_string.parse::<f32>() {
-                    Ok(value) => {
-                        if original_count != value {
-                            self.update_f32(name.clone(), field, value);
-                        }
-                    },
-                    Err(_) => {self.show_error(String::from("Invalid data for bed count"));},
+    fn update_name(&mut self, id: u64, name: String) {
+        self.portfolio.update(id, "rename", |entry| entry.set_name(name));
+    }
+
+    fn undo(&mut self) {
+        if let Some(label) = self.portfolio.undo() {
+            self.drafts.clear();
+            self.notifications.info(format!("Undid {}", label));
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(label) = self.portfolio.redo() {
+            self.drafts.clear();
+            self.notifications.info(format!("Redid {}", label));
+        }
+    }
+
+    fn update_pet_deposit_per_pet(&mut self, id: u64, per_pet: bool) {
+        self.portfolio.update(id, "pet deposit scaling", |entry| entry.set_pet_deposit_per_pet(per_pet));
+    }
+
+    fn update_concessions(&mut self, id: u64, concessions: Vec<Concession>) {
+        self.portfolio.update(id, "concessions", |entry| entry.set_concessions(concessions));
+    }
+
+    fn update_line_items(&mut self, id: u64, line_items: Vec<LineItem>) {
+        self.portfolio.update(id, "recurring fees", |entry| entry.set_line_items(line_items));
+    }
+
+    fn build_line_items_field(&mut self, id: u64, entry: &Entry, ui: &mut Ui) {
+        let expanded = self.is_expanded(id);
+        let arrow = if expanded { "⏷" } else { "⏵" };
+        let total = entry.get_money(Fields::RecurringFees).unwrap_or_default();
+        if ui.button(format!("{} {}", arrow, self.portfolio.get_money_format().format(total))).clicked() {
+            self.toggle_expanded(id);
+        }
+        if !expanded { return; }
+
+        let original = entry.get_line_items();
+        let mut line_items = original.clone();
+        let mut removed: Option<usize> = None;
+        for (index, item) in line_items.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut item.name).desired_width(100.0).hint_text("Fee name"));
+                if let Some(amount) = self.build_money_cell(entry, Cell::LineItem(index), &item.name, item.amount, ui) {
+                    item.amount = amount;
                 }
-            }
+                if ui.button("Remove").clicked() {
+                    removed = Some(index);
+                }
+            });
+        }
+        if let Some(index) = removed {
+            line_items.remove(index);
+            self.forget_drafts(id, |cell| matches!(cell, Cell::LineItem(_)));
+        }
+        if ui.button("Add fee").clicked() {
+            line_items.push(LineItem::default());
+        }
+
+        if line_items != original {
+            self.update_line_items(id, line_items);
         }
     }
 
-    fn load_list(&mut self) -> Vec<Entry> {
-        if self.write_flag { self.write() }
+    fn update_status(&mut self, id: u64, status: Status) {
+        let at = time::now_seconds();
+        self.portfolio.update(id, "status", |entry| entry.set_status(status, at));
+    }
 
-        if self.read_flag { self.read(); }
-        
-        let mut clone: Vec<Entry> = Vec::new();
+    fn update_link(&mut self, id: u64, link: String) {
+        self.portfolio.update(id, "link", |entry| entry.set_link(link));
+    }
 
-        for entry in &self.list {
-            clone.push(entry.clone());
-        }        
+    fn update_split(&mut self, id: u64, policy: SplitPolicy) {
+        self.portfolio.update(id, "split policy", |entry| entry.set_split_policy(policy));
+    }
 
-        clone.sort_by(|a,b| { a.cmp(b, self.sortorder)});
+    fn update_i8(&mut self, id: u64, field: Fields, new_value: i8) {
+        self.portfolio.update(id, &field.label(), |entry| entry.set_i8(field, new_value));
+    }
 
-        clone
+    fn update_f32(&mut self, id: u64, field: Fields, new_value: f32) {
+        self.portfolio.update(id, &field.label(), |entry| entry.set_f32(field, new_value));
     }
 
-    fn insert_new_entry(&mut self) {
-        let mut new_entry = Entry::default();
-        let mut len = self.list.len();
-        loop {
-            let mut new_name = String::from("New_");
-            new_name.push_str(len.to_string().as_str());
-            if self.name_unique(new_name.clone()) {
-                new_entry.set_name(new_name);
-                break;
-            }
-            else {
-                len = len + 1;
-            }
+    fn update_money(&mut self, id: u64, field: Fields, new_value: Money) {
+        self.portfolio.update(id, &field.label(), |entry| entry.set_money(field, new_value));
+    }
 
-            if len > 100 {
-                let mut new_name = String::from("New_");
-                new_name.push_str(len.to_string().as_str());
-                new_entry.set_name(new_name);
-            }
+    fn build_i8_field(&mut self, id: u64, field: Fields, entry: &Entry, ui: &mut Ui) {
+        let original = entry.get_i8(field).unwrap();
+        let shown = if original == 0 { String::new() } else { original.to_string() };
+        let parsed = self.build_draft_field(entry, Cell::Field(field), &field.label(), original, shown, ui, |text| {
+            if text.trim().is_empty() { Ok(0) } else { parse_count(text) }
+        });
+        if let Some(value) = parsed {
+            self.update_i8(id, field, value);
         }
+    }
+
+    fn build_f32_field(&mut self, id: u64, field: Fields, entry: &Entry, ui: &mut Ui) {
+        let original = entry.get_f32(field).unwrap();
+        let shown = if original == 0.0 { String::new() } else { original.to_string() };
+        let parsed = self.build_draft_field(entry, Cell::Field(field), &field.label(), original, shown, ui, |text| {
+            if text.trim().is_empty() { Ok(0.0) } else { parse_percent(text) }
+        });
+        if let Some(value) = parsed {
+            self.update_f32(id, field, value);
+        }
+    }
+
+    fn build_money_field(&mut self, id: u64, field: Fields, entry: &Entry, ui: &mut Ui) {
+        let original = entry.get_money(field).unwrap();
+        if let Some(value) = self.build_money_cell(entry, Cell::Field(field), &field.label(), original, ui) {
+            self.update_money(id, field, value);
+        }
+    }
+
+    fn build_money_cell(&mut self, entry: &Entry, cell: Cell, label: &str, current: Money, ui: &mut Ui) -> Option<Money> {
+        let money_format = self.portfolio.get_money_format();
+        let shown = if current.is_zero() { String::new() } else { money_format.format_input(current) };
+        self.build_draft_field(entry, cell, label, current, shown, ui, |text| {
+            if text.trim().is_empty() { Ok(Money::ZERO) } else { money_format.parse(text) }
+        })
+    }
 
-        self.list.push(new_entry);
-        self.write_flag = true;
+    // A removed item shifts the ones after it, so their drafts would land on the wrong item
+    fn forget_drafts(&mut self, id: u64, forget: impl Fn(&Cell) -> bool) {
+        self.drafts.retain(|(draft_id, cell), _| *draft_id != id || !forget(cell));
     }
 
-    fn recalculate(&mut self) {
-        for entry in self.list.iter_mut() {
-            entry.calculate(self.pet_count);
+    // Returns the value to commit when it's valid and differs from the current one
+    #[allow(clippy::too_many_arguments)]
+    fn build_draft_field<T: PartialEq>(&mut self, entry: &Entry, cell: Cell, label: &str, current: T, shown: String, ui: &mut Ui, parse: impl Fn(&str) -> Result<T, String>) -> Option<T> {
+        let width = ui.spacing().text_edit_width;
+        match build_draft(&mut self.drafts, (entry.get_id(), cell), shown, width, ui, parse)? {
+            Ok(value) => (value != current).then_some(value),
+            Err(problem) => {
+                self.notifications.warn(format!("{} for {} was not saved: {}", label, entry.get_name(), problem));
+                None
+            },
         }
     }
 
-    fn write(&mut self) {
-        self.write_flag = false;
+    fn build_range_bound(&mut self, field: Fields, label: &str, upper: bool, bound: &mut Option<f64>, ui: &mut Ui) {
+        // Amounts are typed the same way as in the cells, counts and percentages are plain numbers
+        let money_format = self.portfolio.get_money_format();
+        let is_money = matches!(field, Fields::Split(_)) || Entry::default().get_money(field).is_some();
+        let shown = match bound {
+            Some(value) if is_money => { money_format.format_input(Money::from_f64(*value)) },
+            Some(value) => { value.to_string() },
+            None => { String::new() },
+        };
+        let parsed = build_draft(&mut self.range_drafts, (field, upper), shown, 80.0, ui, |text| {
+            if text.trim().is_empty() { return Ok(None); }
+            if is_money { return money_format.parse(text).map(|amount| Some(amount.as_f64())); }
+            text.trim().parse::<f64>().map(Some).map_err(|_| String::from("Enter a number, like 1200"))
+        });
+        match parsed {
+            Some(Ok(value)) => { *bound = value; },
+            Some(Err(problem)) => {
+                let which = if upper { "Max" } else { "Min" };
+                self.notifications.warn(format!("{} {} filter was not applied: {}", which, label, problem));
+            },
+            None => {},
+        }
+    }
 
-        let mut properties = JsonValue::new_object();
-        properties[PET_COUNT_KEY] = JsonValue::from(self.pet_count);
+    fn show_filters(&mut self, ui: &mut Ui) {
+        let mut filters = self.portfolio.get_filters().clone();
+        let title = if filters.is_empty() { "Filters" } else { "Filters (active)" };
 
-        let mut saveable = JsonValue::new_object();
-        for entry in &self.list {
-            match save(&entry) {
-                Ok(data) => {
-                    match saveable.insert(&entry.get_name(), data) {
-                        Ok(_) => {},
-                        Err(error) => {println!("Failed to push save file. {}", error)},
+        egui::CollapsingHeader::new(title).id_salt("filters").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Name contains: ");
+                ui.text_edit_singleline(&mut filters.name);
+                ui.label("Link contains: ");
+                ui.text_edit_singleline(&mut filters.link);
+                if ui.button("Clear filters").clicked() {
+                    filters = Filters::default();
+                    self.range_drafts.clear();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Status: ");
+                for status in Status::ALL {
+                    let mut shown = filters.shows(status);
+                    if ui.checkbox(&mut shown, status.label()).changed() {
+                        filters.set_shown(status, shown);
                     }
-                },
-                Err(error) => {
-                    println!("Failed to parse into save file. {}", error)
                 }
+            });
+
+            let mut fields: Vec<(Fields, String)> = Fields::NUMERIC.iter().map(|field| (*field, field.label())).collect();
+            for (index, roommate) in self.portfolio.get_roommates().iter().enumerate() {
+                fields.push((Fields::Split(index), roommate.display_name(index)));
             }
-        }
 
-        let mut total = JsonValue::new_object();
-        total[PROPERTIES_KEY] = properties;
-        total[RENT_DATA_KEY] = saveable;
-    
-        let str_data = total.dump();
-        let data = str_data.as_bytes();
-    
-        let mut data_path = env::current_dir().unwrap();
-        data_path.push("rentdata");
-        data_path.set_extension("json");
-    
-        let data_path_literal = data_path.as_path();
-    
-        if !data_path.exists() {
-            let mut file = File::create(data_path_literal).unwrap();
-            file.write_all(data).unwrap();
+            egui::Grid::new("filter_ranges").show(ui, |ui| {
+                ui.label("Field");
+                ui.label("Min");
+                ui.label("Max");
+                ui.end_row();
+                for (field, label) in fields {
+                    let mut range = filters.get_range(field);
+                    ui.label(&label);
+                    self.build_range_bound(field, &label, false, &mut range.min, ui);
+                    self.build_range_bound(field, &label, true, &mut range.max, ui);
+                    ui.end_row();
+                    filters.set_range(range);
+                }
+            });
+        });
+
+        if &filters != self.portfolio.get_filters() {
+            self.portfolio.set_filters(filters);
         }
-        else {
-            if !data_path.exists() { println!("Error in opening the file!"); return; }
-    
-            let write_result = write(data_path_literal, data);
-            if write_result.is_err() {
-                println!("Failed to write to file! {}", write_result.unwrap_err());
-            }
+    }
+
+    fn sort_header(&mut self, ui: &mut Ui, field: Fields, label: String) {
+        let text = match self.sortorder.indicator(field) {
+            Some(indicator) => format!("{} {}", label, indicator),
+            None => label,
+        };
+        let response = ui.button(text).on_hover_text("Click to sort, click again to reverse. Shift-click to add as a tie-breaker.");
+        if response.clicked() {
+            let add = ui.input(|input| input.modifiers.shift);
+            self.sortorder.click(field, add);
         }
     }
 
-    fn read(&mut self) {
-        self.read_flag = false;
-        let mut new_list: Vec<Entry> = Vec::new();
+    fn show_details(&mut self, ctx: &egui::Context) {
+        let Some(id) = self.details else { return; };
+        let Some(entry) = self.portfolio.get(id).cloned() else {
+            self.details = None;
+            return;
+        };
 
-        let mut data_path = env::current_dir().unwrap();
-        data_path.push("rentdata");
-        data_path.set_extension("json");
+        let roommates = self.portfolio.get_roommates().to_vec();
+        let people = roommates.len();
+        let original = entry.get_split_policy().resized(people);
+        let mut policy = original.clone();
+        let original_concessions = entry.get_concessions();
+        let mut concessions = original_concessions.clone();
+        let mut open = true;
+
+        egui::Window::new(format!("Details: {}", entry.get_name()))
+            .id(egui::Id::new(("details", id)))
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.heading("Rent split");
+
+                let mut key = policy.key();
+                egui::ComboBox::from_label("Policy")
+                    .selected_text(SplitPolicy::label(key))
+                    .show_ui(ui, |ui| {
+                        for option in SplitPolicy::ALL_KEYS {
+                            ui.selectable_value(&mut key, option, SplitPolicy::label(option));
+                        }
+                    });
+                if key != policy.key() {
+                    policy = SplitPolicy::from_key(key).resized(people);
+                }
 
-        let data_path_literal = data_path.as_path();
+                match &mut policy {
+                    SplitPolicy::Equal => {
+                        ui.label("Everyone pays the same share.");
+                    },
+                    SplitPolicy::SquareFootage(values) => {
+                        for (index, value) in values.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.label(format!("{} bedroom sq. ft.: ", roommates[index].display_name(index)));
+                                ui.add(egui::DragValue::new(value).range(0.0..=f32::MAX));
+                            });
+                        }
+                    },
+                    SplitPolicy::Shares(values) => {
+                        // Shares count relative to each other, so show what each works out to when they don't add up to 100
+                        let sum: f32 = values.iter().sum();
+                        let normalized: Vec<f32> = values.iter().map(|value| if sum > 0.0 { value / sum * 100.0 } else { 100.0 / people as f32 }).collect();
+                        let scaled = (sum - 100.0).abs() > 0.001;
+                        for (index, value) in values.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.label(format!("{} share: ", roommates[index].display_name(index)));
+                                ui.add(egui::DragValue::new(value).range(0.0..=100.0).suffix("%"));
+                                if scaled {
+                                    ui.label(format!("→ {:.1}%", normalized[index]));
+                                }
+                            });
+                        }
+                        if scaled {
+                            ui.label(format!("The shares add up to {}%, so they are scaled to 100%.", sum));
+                        }
+                    },
+                    SplitPolicy::Rooms(rooms) => {
+                        ui.label("Each room's rent is paid by its roommate; anything left over is split evenly.");
+                        for (index, room) in rooms.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.label(format!("{} room: ", roommates[index].display_name(index)));
+                                ui.text_edit_singleline(&mut room.name);
+                                ui.label("Rent: ");
+                                let label = format!("{} room rent", roommates[index].display_name(index));
+                                if let Some(rent) = self.build_money_cell(&entry, Cell::Room(index), &label, room.rent, ui) {
+                                    room.rent = rent;
+                                }
+                            });
+                        }
+                    },
+                }
 
-        if !data_path.exists() {
-            let mut file = File::create(data_path_literal).unwrap();
-            file.write_all(String::from("{}").as_bytes()).unwrap();
+                ui.separator();
+                ui.heading("Status");
+                ui.label(format!("Currently {}", entry.get_status().label()));
+                for transition in entry.get_status_history().iter().rev() {
+                    ui.label(format!("{}: {}", time::readable_timestamp(transition.at), transition.status.label()));
+                }
+
+                ui.separator();
+                ui.heading("Concessions");
+                let mut removed: Option<usize> = None;
+                for (index, concession) in concessions.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_salt(("concession", index))
+                            .selected_text(concession.kind.label())
+                            .show_ui(ui, |ui| {
+                                for kind in ConcessionKind::ALL {
+                                    ui.selectable_value(&mut concession.kind, kind, kind.label());
+                                }
+                            });
+                        let label = concession.kind.label();
+                        if concession.kind.is_free_time() {
+                            let shown = if concession.count == 0 { String::new() } else { concession.count.to_string() };
+                            let parsed = self.build_draft_field(&entry, Cell::Concession(index), label, concession.count, shown, ui, |text| {
+                                if text.trim().is_empty() { Ok(0) } else { parse_count(text) }
+                            });
+                            if let Some(count) = parsed {
+                                concession.count = count;
+                            }
+                        }
+                        else if let Some(amount) = self.build_money_cell(&entry, Cell::Concession(index), label, concession.amount, ui) {
+                            concession.amount = amount;
+                        }
+                        if ui.button("Remove").clicked() {
+                            removed = Some(index);
+                        }
+                    });
+                }
+                if let Some(index) = removed {
+                    concessions.remove(index);
+                    self.forget_drafts(id, |cell| matches!(cell, Cell::Concession(_)));
+                }
+                if ui.button("Add concession").clicked() {
+                    concessions.push(Concession::default());
+                }
+
+                ui.separator();
+                ui.heading("Per-person breakdown");
+                let format = self.portfolio.get_money_format();
+                let deposits = entry.get_deposit_splits();
+                let move_in = entry.get_move_in_splits();
+                egui::Grid::new("breakdown").show(ui, |ui| {
+                    ui.label("Roommate");
+                    ui.label("Monthly");
+                    ui.label("Deposit");
+                    ui.label("Move-in");
+                    ui.end_row();
+                    for (index, roommate) in roommates.iter().enumerate() {
+                        ui.label(roommate.display_name(index));
+                        ui.label(format.format(entry.get_money(Fields::Split(index)).unwrap_or_default()));
+                        ui.label(format.format(deposits.get(index).copied().unwrap_or_default()));
+                        ui.label(format.format(move_in.get(index).copied().unwrap_or_default()));
+                        ui.end_row();
+                    }
+                });
+            });
+
+        if policy != original {
+            self.update_split(id, policy);
+        }
+        if concessions != original_concessions {
+            self.update_concessions(id, concessions);
         }
+        if !open {
+            self.details = None;
+        }
+    }
 
-        //println!("Opening!");
-        let data = jzon::parse(read_to_string(data_path_literal).unwrap().as_str()).unwrap();
+    fn load_list(&mut self) -> Vec<Entry> {
+        if self.portfolio.is_dirty() { self.save(); }
 
-        if !data.is_empty() {
-            match &data[PROPERTIES_KEY][PET_COUNT_KEY].as_i8() {
-                Some(value) => { self.pet_count = value.clone(); },
-                None => { println!("Failed to parse pet count from file"); }
-            }
+        self.portfolio.visible(&self.sortorder)
+    }
 
-            let rental_data = &data[RENT_DATA_KEY];
-            for (name, data ) in rental_data.entries() {
-                match build(name, data) {
-                    Some(entry) => { new_list.push(entry);},
-                    None => { println!("Failed to build from data!") }
-                }
+    fn save(&mut self) {
+        for problem in self.portfolio.save() {
+            if problem.is_fatal() {
+                self.notifications.error(problem.to_string());
+            }
+            else {
+                self.notifications.warn(problem.to_string());
             }
         }
-        self.list = new_list; 
-        self.recalculate();
+    }
+
+    // Loading starts a fresh history, so undo buttons from before can't apply anymore
+    fn report_load(&mut self, report: LoadReport) {
+        self.notifications.forget_undo();
+        let path = self.portfolio.get_path().display().to_string();
+        if let Some(problem) = report.problems.iter().find(|problem| problem.is_fatal()) {
+            self.notifications.error(format!("Failed to load {}: {}", path, problem));
+        }
+        else if !report.problems.is_empty() {
+            self.notifications.warn(format!("Loaded {} with {} problem(s)", path, report.problems.len()));
+        }
+        if let Some(version) = report.upgraded_from {
+            self.notifications.info(format!("Upgraded the data file from format {} to {}", version, migrations::CURRENT_VERSION));
+        }
+        self.load_errors = report.problems;
+        self.quarantined = report.quarantined;
     }
 }
 
 impl eframe::App for MyApp {
     fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
-        self.write();
+        self.save();
     }
 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // A focused text field keeps Ctrl+Z for its own text
+        if ctx.memory(|memory| memory.focused().is_none()) {
+            if ctx.input_mut(|input| input.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::Z)) {
+                self.redo();
+            }
+            else if ctx.input_mut(|input| input.consume_key(egui::Modifiers::COMMAND, egui::Key::Z)) {
+                self.undo();
+            }
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             ctx.set_pixels_per_point(ZOOM);
 
             //ctx.set_fonts(fonts);
 
+            self.show_workspace_bar(ui);
+
+            ui.horizontal(|ui| {
+                let mut money_format = self.portfolio.get_money_format();
+                egui::ComboBox::from_label("Currency")
+                    .selected_text(money_format.currency.code())
+                    .show_ui(ui, |ui| {
+                        for currency in Currency::ALL {
+                            ui.selectable_value(&mut money_format.currency, currency, currency.code());
+                        }
+                    });
+                egui::ComboBox::from_label("Locale")
+                    .selected_text(money_format.locale.code())
+                    .show_ui(ui, |ui| {
+                        for locale in Locale::ALL {
+                            ui.selectable_value(&mut money_format.locale, locale, locale.code());
+                        }
+                    });
+                if money_format != self.portfolio.get_money_format() {
+                    self.portfolio.set_money_format(money_format);
+                }
+            });
+
             ui.horizontal(|ui| {
-                ui.label("Pet count: ");
+                ui.label("Roommates: ");
 
-                let mut pet_str = self.pet_count.to_string();
-                let response = ui.text_edit_singleline(&mut pet_str);
+                let count = self.portfolio.get_roommates().len();
+                let mut roommate_str = count.to_string();
+                let response = ui.text_edit_singleline(&mut roommate_str);
                 if response.changed() || response.lost_focus() {
-                    if pet_str.is_empty() {
-                        self.pet_count = 0;
-                    }
-                    else {
-                        match pet_str.parse::<i8>() {
-                            Ok(new) => {
-                                if new != self.pet_count {
-                                    self.pet_count = new;
-                                    self.recalculate();
-                                }
-                            },
-                            Err(_) => {},
+                    if let Ok(new) = roommate_str.parse::<i8>() {
+                        if new >= 1 && new as usize != count {
+                            let mut roommates = self.portfolio.get_roommates().to_vec();
+                            roommates.resize(new as usize, Roommate::default());
+                            self.portfolio.set_roommates(roommates);
+                            self.sortorder.retain(|field| !matches!(field, Fields::Split(index) if index >= new as usize));
                         }
                     }
                 }
             });
 
+            egui::CollapsingHeader::new("Roster").show(ui, |ui| {
+                let mut roommates = self.portfolio.get_roommates().to_vec();
+                let mut changed = false;
+                egui::Grid::new("roster").show(ui, |ui| {
+                    for (index, roommate) in roommates.iter_mut().enumerate() {
+                        ui.label(format!("Roommate {}: ", index + 1));
+                        changed |= ui.text_edit_singleline(&mut roommate.name).changed();
+                        ui.label("Pets: ");
+                        changed |= ui.add(egui::DragValue::new(&mut roommate.pets).range(0..=i8::MAX)).changed();
+                        changed |= ui.checkbox(&mut roommate.needs_parking, "Needs parking").changed();
+                        ui.end_row();
+                    }
+                });
+                if changed {
+                    self.portfolio.set_roommates(roommates);
+                }
+            });
+
+            self.show_filters(ui);
+
             if ui.button("Add Entry").clicked() {
-                self.insert_new_entry();
+                self.portfolio.new_entry();
                 //Create a popup to fill in the data!
             }
+            let split_count = self.portfolio.get_roommates().len();
+            let money_format = self.portfolio.get_money_format();
             TableBuilder::new(ui)
-                .column(Column::auto().resizable(true))
-                .column(Column::auto().resizable(true))
-                .column(Column::auto().resizable(true))
-                .column(Column::auto().resizable(true))
-                .column(Column::auto().resizable(true))
-                .column(Column::auto().resizable(true))
-                .column(Column::auto().resizable(true))
-                .column(Column::auto().resizable(true))
-                .column(Column::auto().resizable(true))
-                .column(Column::auto().resizable(true))
-                .column(Column::auto().resizable(true))
-                .column(Column::auto().resizable(true))
+                .columns(Column::auto().resizable(true), 19)
+                .columns(Column::auto().resizable(true), split_count)
                 .column(Column::auto().resizable(true))
                 .column(Column::auto().resizable(true))
                 .column(Column::remainder())
                 .header(30.0, |mut header| {
                     header.col(|ui| {
-                        if ui.button("Name").clicked() {
-                            self.sortorder = Fields::Name;
-                        };
+                        self.sort_header(ui, Fields::Name, Fields::Name.label());
+                    });
+                    header.col(|ui| {
+                        self.sort_header(ui, Fields::Status, Fields::Status.label());
+                    });
+                    header.col(|ui| {
+                        self.sort_header(ui, Fields::Beds, Fields::Beds.label());
+                    });
+                    header.col(|ui| {
+                        self.sort_header(ui, Fields::Baths, Fields::Baths.label());
+                    });
+                    header.col(|ui| {
+                        self.sort_header(ui, Fields::Deposit, Fields::Deposit.label());
+                    });
+                    header.col(|ui| {
+                        self.sort_header(ui, Fields::PetDeposit, Fields::PetDeposit.label());
                     });
                     header.col(|ui| {
-                        if ui.button("Number of Beds").clicked() {
-                            self.sortorder = Fields::Beds;
-                        };
+                        self.sort_header(ui, Fields::PetFee, Fields::PetFee.label());
                     });
                     header.col(|ui| {
-                        if ui.button("Number of Baths").clicked() {
-                            self.sortorder = Fields::Baths;
-                        };
+                        self.sort_header(ui, Fields::MoveInFees, Fields::MoveInFees.label());
                     });
                     header.col(|ui| {
-                        if ui.button("Deposit").clicked() {
-                            self.sortorder = Fields::Deposit;
-                        };
+                        self.sort_header(ui, Fields::PetMonthly, Fields::PetMonthly.label());
                     });
                     header.col(|ui| {
-                        if ui.button("Pet Deposit").clicked() {
-                            self.sortorder = Fields::PetDeposit;
-                        };
+                        self.sort_header(ui, Fields::ParkingSpots, Fields::ParkingSpots.label());
                     });
                     header.col(|ui| {
-                        if ui.button("Pet Monthly").clicked() {
-                            self.sortorder = Fields::PetMonthly;
-                        };
+                        self.sort_header(ui, Fields::ParkingMonthly, Fields::ParkingMonthly.label());
                     });
                     header.col(|ui| {
-                        if ui.button("Parking Monthly").clicked() {
-                            self.sortorder = Fields::ParkingMonthly;
-                        };
+                        self.sort_header(ui, Fields::MonthlyRent, Fields::MonthlyRent.label());
                     });
                     header.col(|ui| {
-                        if ui.button("Monthly Rent").clicked() {
-                            self.sortorder = Fields::MonthlyRent;
-                        };
+                        self.sort_header(ui, Fields::RecurringFees, Fields::RecurringFees.label());
                     });
                     header.col(|ui| {
-                        if ui.button("Total Rent").clicked() {
-                            self.sortorder = Fields::TotalRent;
-                        };
+                        self.sort_header(ui, Fields::EffectiveRent, Fields::EffectiveRent.label());
                     });
                     header.col(|ui| {
-                        if ui.button("Rent for 2").clicked() {
-                            self.sortorder = Fields::RentFor2;
-                        };
+                        self.sort_header(ui, Fields::LeaseMonths, Fields::LeaseMonths.label());
                     });
                     header.col(|ui| {
-                        if ui.button("Rent for 3").clicked() {
-                            self.sortorder = Fields::RentFor3;
-                        };
+                        self.sort_header(ui, Fields::DepositRefund, Fields::DepositRefund.label());
                     });
                     header.col(|ui| {
-                        if ui.button("Rent for 4").clicked() {
-                            self.sortorder = Fields::RentFor4;
-                        };
+                        self.sort_header(ui, Fields::TotalRent, Fields::TotalRent.label());
                     });
+                    header.col(|ui| {
+                        self.sort_header(ui, Fields::MoveInTotal, Fields::MoveInTotal.label());
+                    });
+                    header.col(|ui| {
+                        self.sort_header(ui, Fields::LeaseCost, Fields::LeaseCost.label());
+                    });
+                    for index in 0..split_count {
+                        header.col(|ui| {
+                            self.sort_header(ui, Fields::Split(index), self.portfolio.get_roommates()[index].display_name(index));
+                        });
+                    }
                     header.col(|ui| {
                         ui.heading("Link");
                     });
+                    header.col(|ui| {
+                        ui.heading("Details");
+                    });
                     header.col(|ui| {
                         ui.heading("Delete");
                     });
@@ -518,68 +1199,124 @@ impl eframe::App for MyApp {
                     let list = self.load_list();
                     for entry in &list {
                         let cloned = entry.clone();
-                        let name = entry.get_name();
-                        body.row(30.0, |mut row: egui_extras::TableRow<'_, '_>| {
+                        let id = entry.get_id();
+                        let height = if self.is_expanded(id) { 30.0 * (entry.get_line_items().len() as f32 + 2.0) } else { 30.0 };
+                        body.row(height, |mut row: egui_extras::TableRow<'_, '_>| {
                             row.col(|ui| {
                                 let mut name = String::from(&cloned.get_name());
                                 let response = ui.add(egui::TextEdit::singleline(&mut name));
-                                if response.changed() || response.lost_focus() {
-                                    if self.name_unique(name.clone()) && !&cloned.is(&name) {
-                                        self.update_name(entry.get_name().clone(), name);
-                                    }
+                                if (response.changed() || response.lost_focus()) && cloned.get_name() != name {
+                                    self.update_name(id, name);
                                 }
                             });
                             row.col(|ui: &mut egui::Ui| {
-                                self.build_i8_field(&name, Fields::Beds, entry, ui);
+                                let mut status = entry.get_status();
+                                egui::ComboBox::from_id_salt(("status", id))
+                                    .selected_text(status.label())
+                                    .show_ui(ui, |ui| {
+                                        for option in Status::ALL {
+                                            ui.selectable_value(&mut status, option, option.label());
+                                        }
+                                    });
+                                if status != entry.get_status() {
+                                    self.update_status(id, status);
+                                }
                             });
                             row.col(|ui: &mut egui::Ui| {
-                                self.build_i8_field(&name, Fields::Baths, entry, ui);
+                                self.build_i8_field(id, Fields::Beds, entry, ui);
                             });
                             row.col(|ui: &mut egui::Ui| {
-                                self.build_f32_field(&name, Fields::Deposit, entry, ui);
+                                self.build_i8_field(id, Fields::Baths, entry, ui);
                             });
                             row.col(|ui: &mut egui::Ui| {
-                                self.build_f32_field(&name, Fields::PetDeposit, entry, ui);
+                                self.build_money_field(id, Fields::Deposit, entry, ui);
                             });
                             row.col(|ui: &mut egui::Ui| {
-                                self.build_f32_field(&name, Fields::PetMonthly, entry, ui);
+                                ui.horizontal(|ui| {
+                                    self.build_money_field(id, Fields::PetDeposit, entry, ui);
+                                    let mut per_pet = entry.is_pet_deposit_per_pet();
+                                    if ui.checkbox(&mut per_pet, "per pet").changed() {
+                                        self.update_pet_deposit_per_pet(id, per_pet);
+                                    }
+                                });
+                            });
+                            row.col(|ui: &mut egui::Ui| {
+                                self.build_money_field(id, Fields::PetFee, entry, ui);
+                            });
+                            row.col(|ui: &mut egui::Ui| {
+                                self.build_money_field(id, Fields::MoveInFees, entry, ui);
+                            });
+                            row.col(|ui: &mut egui::Ui| {
+                                self.build_money_field(id, Fields::PetMonthly, entry, ui);
+                            });
+                            row.col(|ui: &mut egui::Ui| {
+                                self.build_i8_field(id, Fields::ParkingSpots, entry, ui);
+                            });
+                            row.col(|ui: &mut egui::Ui| {
+                                self.build_money_field(id, Fields::ParkingMonthly, entry, ui);
+                            });
+                            row.col(|ui: &mut egui::Ui| {
+                                self.build_money_field(id, Fields::MonthlyRent, entry, ui);
+                            });
+                            row.col(|ui: &mut egui::Ui| {
+                                ui.vertical(|ui| {
+                                    self.build_line_items_field(id, entry, ui);
+                                });
                             });
                             row.col(|ui: &mut egui::Ui| {
-                                self.build_f32_field(&name, Fields::ParkingMonthly, entry, ui);
+                                ui.add(egui::Label::new(money_format.format(entry.get_money(Fields::EffectiveRent).unwrap())));
                             });
                             row.col(|ui: &mut egui::Ui| {
-                                self.build_f32_field(&name, Fields::MonthlyRent, entry, ui);
+                                self.build_i8_field(id, Fields::LeaseMonths, entry, ui);
                             });
                             row.col(|ui: &mut egui::Ui| {
-                                ui.add(egui::Label::new(entry.get_f32(Fields::TotalRent).unwrap().to_string()));
+                                self.build_f32_field(id, Fields::DepositRefund, entry, ui);
                             });
                             row.col(|ui: &mut egui::Ui| {
-                                ui.add(egui::Label::new(entry.get_f32(Fields::RentFor2).unwrap().to_string()));
+                                ui.add(egui::Label::new(money_format.format(entry.get_money(Fields::TotalRent).unwrap())));
                             });
                             row.col(|ui: &mut egui::Ui| {
-                                ui.add(egui::Label::new(entry.get_f32(Fields::RentFor3).unwrap().to_string()));
+                                ui.add(egui::Label::new(money_format.format(entry.get_money(Fields::MoveInTotal).unwrap())));
                             });
                             row.col(|ui: &mut egui::Ui| {
-                                ui.add(egui::Label::new(entry.get_f32(Fields::RentFor4).unwrap().to_string()));
+                                ui.add(egui::Label::new(money_format.format(entry.get_money(Fields::LeaseCost).unwrap())));
                             });
+                            for index in 0..split_count {
+                                row.col(|ui: &mut egui::Ui| {
+                                    let split = entry.get_money(Fields::Split(index)).unwrap_or_default();
+                                    ui.add(egui::Label::new(money_format.format(split)));
+                                });
+                            }
                             row.col(|ui: &mut egui::Ui| {
                                 let mut link = String::from(&cloned.get_link());
                                 let response = ui.add(egui::TextEdit::singleline(&mut link));
-                                if response.changed() || response.lost_focus() {
-                                    if !&cloned.get_link().eq_ignore_ascii_case(&link) {
-                                        self.update_link(name.clone(), link);
-                                    }
+                                if (response.changed() || response.lost_focus())
+                                    && !cloned.get_link().eq_ignore_ascii_case(&link) {
+                                    self.update_link(id, link);
+                                }
+                            });
+                            row.col(|ui: &mut egui::Ui| {
+                                if ui.add(egui::Button::new("Details")).clicked() {
+                                    self.details = Some(id);
                                 }
                             });
                             row.col(|ui: &mut egui::Ui| {
                                 let response = ui.add(egui::Button::new("Delete"));
                                 if response.clicked() {
-                                    self.remove(name.clone());
+                                    self.remove(id);
                                 }
                             });
                         });
                     }
                 });
         });
+
+        self.show_details(ctx);
+        self.show_path_prompt(ctx);
+        self.show_backups(ctx);
+        self.show_import_wizard(ctx);
+        self.show_load_errors(ctx);
+        self.show_log(ctx);
+        self.show_toasts(ctx);
     }
 }
\ No newline at end of file
@@ -0,0 +1,133 @@
+use jzon::JsonValue;
+
+pub const CURRENT_VERSION: i32 = 2;
+pub const VERSION_KEY: &str = "version";
+
+// Keys as the older formats spelled them, so renaming a key in store.rs can't change what a migration does
+const PROPERTIES_KEY: &str = "properties";
+const PET_COUNT_KEY: &str = "petcount";
+const ROOMMATE_COUNT_KEY: &str = "roommatecount";
+const ROOMMATES_KEY: &str = "roommates";
+const ROOMMATE_PETS_KEY: &str = "pets";
+const RENT_DATA_KEY: &str = "rentdata";
+const NAME_KEY: &str = "name";
+const NEXT_ID_KEY: &str = "nextid";
+const DEFAULT_ROOMMATE_COUNT: usize = 2;
+
+// MIGRATIONS[n] upgrades a version n document to version n + 1
+const MIGRATIONS: [fn(&mut JsonValue); CURRENT_VERSION as usize] = [
+    roster_from_counts,
+    entries_keyed_by_id,
+];
+
+// Files from before versioning have no version key and count as version 0
+pub fn version(data: &JsonValue) -> i32 {
+    data[PROPERTIES_KEY][VERSION_KEY].as_i32().unwrap_or(0)
+}
+
+// Upgrades the document in place and returns the version it was written with
+pub fn migrate(data: &mut JsonValue) -> i32 {
+    let found = version(data);
+    if found >= CURRENT_VERSION { return found; }
+
+    for step in MIGRATIONS.iter().skip(found.max(0) as usize) {
+        step(data);
+    }
+    data[PROPERTIES_KEY][VERSION_KEY] = JsonValue::from(CURRENT_VERSION);
+
+    found
+}
+
+// Version 0 kept a roommate count and a household pet count instead of a roster
+fn roster_from_counts(data: &mut JsonValue) {
+    let properties = &mut data[PROPERTIES_KEY];
+
+    if !properties[ROOMMATES_KEY].is_array() || properties[ROOMMATES_KEY].is_empty() {
+        let count = properties[ROOMMATE_COUNT_KEY].as_i8().map(|count| count.max(1) as usize).unwrap_or(DEFAULT_ROOMMATE_COUNT);
+        let mut roster = JsonValue::new_array();
+        for _ in 0..count {
+            let _ = roster.push(JsonValue::new_object());
+        }
+        properties[ROOMMATES_KEY] = roster;
+    }
+
+    // The whole household's pets go to the first roommate, the roster can spread them out afterwards
+    if let Some(pets) = properties[PET_COUNT_KEY].as_i8() {
+        if !properties[ROOMMATES_KEY].members().any(|roommate| roommate.has_key(ROOMMATE_PETS_KEY)) {
+            properties[ROOMMATES_KEY][0][ROOMMATE_PETS_KEY] = JsonValue::from(pets);
+        }
+    }
+
+    properties.remove(PET_COUNT_KEY);
+    properties.remove(ROOMMATE_COUNT_KEY);
+}
+
+// Version 1 keyed listings by name, from 2 they're keyed by a numeric ID and carry their name
+fn entries_keyed_by_id(data: &mut JsonValue) {
+    let listings = data[RENT_DATA_KEY].take();
+    let mut rent_data = JsonValue::new_object();
+    let mut next_id: u64 = 1;
+
+    for (name, listing) in listings.entries() {
+        let mut listing = listing.clone();
+        if listing.is_object() {
+            listing[NAME_KEY] = JsonValue::from(name);
+        }
+        rent_data[next_id.to_string().as_str()] = listing;
+        next_id += 1;
+    }
+
+    data[RENT_DATA_KEY] = rent_data;
+    data[PROPERTIES_KEY][NEXT_ID_KEY] = JsonValue::from(next_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unversioned_counts_become_a_roster() {
+        let mut data = jzon::parse(r#"{"properties":{"petcount":2,"roommatecount":3},"rentdata":{}}"#).unwrap();
+
+        assert_eq!(migrate(&mut data), 0);
+        assert_eq!(version(&data), CURRENT_VERSION);
+
+        let roommates = &data[PROPERTIES_KEY][ROOMMATES_KEY];
+        assert_eq!(roommates.len(), 3);
+        assert_eq!(roommates[0][ROOMMATE_PETS_KEY].as_i8(), Some(2));
+        assert!(!roommates[1].has_key(ROOMMATE_PETS_KEY));
+        assert!(!data[PROPERTIES_KEY].has_key(PET_COUNT_KEY));
+        assert!(!data[PROPERTIES_KEY].has_key(ROOMMATE_COUNT_KEY));
+    }
+
+    #[test]
+    fn original_files_get_the_default_household() {
+        let mut data = jzon::parse(r#"{"properties":{"petcount":1},"rentdata":{}}"#).unwrap();
+        migrate(&mut data);
+
+        assert_eq!(data[PROPERTIES_KEY][ROOMMATES_KEY].len(), DEFAULT_ROOMMATE_COUNT);
+        assert_eq!(data[PROPERTIES_KEY][ROOMMATES_KEY][0][ROOMMATE_PETS_KEY].as_i8(), Some(1));
+    }
+
+    #[test]
+    fn listings_move_from_name_keys_to_ids() {
+        let mut data = jzon::parse(r#"{"properties":{"version":1},"rentdata":{"Oak":{"beds":2},"Pine":{"beds":1}}}"#).unwrap();
+        migrate(&mut data);
+
+        let listings = &data[RENT_DATA_KEY];
+        assert_eq!(listings["1"][NAME_KEY].as_str(), Some("Oak"));
+        assert_eq!(listings["2"][NAME_KEY].as_str(), Some("Pine"));
+        assert_eq!(listings["2"]["beds"].as_i8(), Some(1));
+        assert!(!listings.has_key("Oak"));
+        assert_eq!(data[PROPERTIES_KEY][NEXT_ID_KEY].as_u64(), Some(3));
+    }
+
+    #[test]
+    fn current_and_newer_documents_are_left_alone() {
+        let text = r#"{"properties":{"version":99,"petcount":1},"rentdata":{}}"#;
+        let mut data = jzon::parse(text).unwrap();
+
+        assert_eq!(migrate(&mut data), 99);
+        assert_eq!(data, jzon::parse(text).unwrap());
+    }
+}
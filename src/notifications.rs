@@ -0,0 +1,161 @@
+use std::time::{Duration, Instant};
+
+pub const TOAST_DURATION: Duration = Duration::from_secs(5);
+const MAX_LOG: usize = 200;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Level {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Level {
+    pub fn label(self) -> &'static str {
+        match self {
+            Level::Info => "Info",
+            Level::Warning => "Warning",
+            Level::Error => "Error",
+        }
+    }
+}
+
+pub struct Notification {
+    pub level: Level,
+    pub message: String,
+    pub repeats: u32,
+    pub created: Instant,
+    pub dismissed: bool,
+    pub undo_step: Option<u64>,
+}
+
+#[derive(Default)]
+pub struct Notifications {
+    log: Vec<Notification>,
+}
+
+impl Notifications {
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.push(Level::Info, message.into());
+    }
+
+    pub fn warn(&mut self, message: impl Into<String>) {
+        self.push(Level::Warning, message.into());
+    }
+
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.push(Level::Error, message.into());
+    }
+
+    // Offers an undo button for as long as `undo_step` is the next command to undo
+    pub fn undoable(&mut self, message: impl Into<String>, undo_step: u64) {
+        self.add(Level::Info, message.into(), Some(undo_step));
+    }
+
+    // Another workspace has its own history, so older undo buttons would undo the wrong thing
+    pub fn forget_undo(&mut self) {
+        for notification in self.log.iter_mut() {
+            notification.undo_step = None;
+        }
+    }
+
+    pub fn push(&mut self, level: Level, message: String) {
+        self.add(level, message, None);
+    }
+
+    // Fields re-report on every frame they stay invalid, so repeats fold into the last message
+    fn add(&mut self, level: Level, message: String, undo_step: Option<u64>) {
+        if let Some(last) = self.log.last_mut().filter(|last| last.level == level && last.message == message && last.undo_step == undo_step) {
+            last.repeats += 1;
+            last.created = Instant::now();
+            last.dismissed = false;
+            return;
+        }
+
+        self.log.push(Notification { level, message, repeats: 1, created: Instant::now(), dismissed: false, undo_step });
+        if self.log.len() > MAX_LOG {
+            self.log.remove(0);
+        }
+    }
+
+    // Keeps this log's history ahead of another's, e.g. across switching workspaces
+    pub fn absorb(&mut self, other: Notifications) {
+        self.log.extend(other.log);
+        let excess = self.log.len().saturating_sub(MAX_LOG);
+        self.log.drain(..excess);
+    }
+
+    pub fn get_log(&self) -> &[Notification] {
+        &self.log
+    }
+
+    // Index into the log and the notification, for toasts that haven't expired or been closed
+    pub fn get_toasts(&self, now: Instant) -> Vec<(usize, &Notification)> {
+        self.log.iter().enumerate()
+            .filter(|(_, notification)| !notification.dismissed && now.duration_since(notification.created) < TOAST_DURATION)
+            .collect()
+    }
+
+    pub fn dismiss(&mut self, index: usize) {
+        if let Some(notification) = self.log.get_mut(index) {
+            notification.dismissed = true;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.log.clear();
+    }
+
+    pub fn count(&self, level: Level) -> usize {
+        self.log.iter().filter(|notification| notification.level == level).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_messages_fold_together() {
+        let mut notifications = Notifications::default();
+        notifications.error("Beds must be a whole number");
+        notifications.error("Beds must be a whole number");
+        notifications.info("Saved");
+        notifications.error("Beds must be a whole number");
+
+        let log = notifications.get_log();
+        assert_eq!(log.len(), 3);
+        assert_eq!(log[0].repeats, 2);
+        assert_eq!(notifications.count(Level::Error), 2);
+    }
+
+    #[test]
+    fn undoable_messages_keep_their_step() {
+        let mut notifications = Notifications::default();
+        notifications.undoable("Deleted Oak", 1);
+        notifications.undoable("Deleted Oak", 3);
+
+        let log = notifications.get_log();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[1].undo_step, Some(3));
+
+        notifications.forget_undo();
+        assert!(notifications.get_log().iter().all(|notification| notification.undo_step.is_none()));
+    }
+
+    #[test]
+    fn toasts_expire_and_can_be_dismissed() {
+        let mut notifications = Notifications::default();
+        notifications.warn("first");
+        notifications.warn("second");
+        let now = Instant::now();
+
+        notifications.dismiss(0);
+        let toasts = notifications.get_toasts(now);
+        assert_eq!(toasts.len(), 1);
+        assert_eq!(toasts[0].1.message, "second");
+
+        assert!(notifications.get_toasts(now + TOAST_DURATION).is_empty());
+        assert_eq!(notifications.get_log().len(), 2);
+    }
+}
@@ -0,0 +1,67 @@
+use crate::objects::money::Money;
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum ConcessionKind {
+    #[default]
+    FreeWeeks,
+    FreeMonths,
+    Credit,
+    WaivedFee,
+}
+
+// Free time is counted in whole weeks or months, credits and waived fees are an amount of money
+#[derive(Clone, PartialEq, Default)]
+pub struct Concession {
+    pub kind: ConcessionKind,
+    pub count: i8,
+    pub amount: Money,
+}
+
+impl ConcessionKind {
+    pub const ALL: [ConcessionKind; 4] = [ConcessionKind::FreeWeeks, ConcessionKind::FreeMonths, ConcessionKind::Credit, ConcessionKind::WaivedFee];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            ConcessionKind::FreeWeeks => "freeweeks",
+            ConcessionKind::FreeMonths => "freemonths",
+            ConcessionKind::Credit => "credit",
+            ConcessionKind::WaivedFee => "waivedfee",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConcessionKind::FreeWeeks => "Free weeks",
+            ConcessionKind::FreeMonths => "Free months",
+            ConcessionKind::Credit => "Rent credit",
+            ConcessionKind::WaivedFee => "Waived fee",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        ConcessionKind::ALL.into_iter().find(|kind| kind.key() == key)
+    }
+
+    pub fn is_free_time(&self) -> bool {
+        matches!(self, ConcessionKind::FreeWeeks | ConcessionKind::FreeMonths)
+    }
+}
+
+impl Concession {
+    // How much rent this takes off the lease; waived fees come off the one-time fees instead
+    pub fn rent_value(&self, monthly_rent: Money) -> Money {
+        match self.kind {
+            ConcessionKind::FreeWeeks => monthly_rent.scale(12.0 / 52.0 * self.count as f64),
+            ConcessionKind::FreeMonths => monthly_rent.times(self.count as i64),
+            ConcessionKind::Credit => self.amount,
+            ConcessionKind::WaivedFee => Money::ZERO,
+        }
+    }
+
+    pub fn fee_value(&self) -> Money {
+        match self.kind {
+            ConcessionKind::WaivedFee => self.amount,
+            _ => Money::ZERO,
+        }
+    }
+}
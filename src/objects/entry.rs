@@ -1,92 +1,239 @@
 use std::cmp::Ordering;
+use crate::objects::concession::Concession;
 use crate::objects::fields::Fields;
+use crate::objects::line_item::LineItem;
+use crate::objects::money::Money;
+use crate::objects::roommate::Roommate;
+use crate::objects::split::SplitPolicy;
+use crate::objects::status::{Status, Transition};
 
 
 #[derive(Clone)]
 pub struct Entry {
+    id: u64,
     name: String,
     beds: i8,
     baths: i8,
-    deposit: f32,
-    pet_deposit: f32,
-    pet_monthly: f32,
-    parking_monthly: f32,
-    monthly_rent: f32,
-    total_rent: f32,
-    rent_for_2: f32,
-    rent_for_3: f32,
-    rent_for_4: f32,
+    deposit: Money,
+    pet_deposit: Money,
+    pet_deposit_per_pet: bool,
+    pet_fee: Money,
+    move_in_fees: Money,
+    pet_monthly: Money,
+    parking_spots: i8,
+    parking_monthly: Money,
+    monthly_rent: Money,
+    lease_months: i8,
+    deposit_refund: f32,
+    concessions: Vec<Concession>,
+    line_items: Vec<LineItem>,
+    effective_rent: Money,
+    recurring_fees: Money,
+    total_rent: Money,
+    move_in_total: Money,
+    lease_cost: Money,
+    splits: Vec<Money>,
+    deposit_splits: Vec<Money>,
+    move_in_splits: Vec<Money>,
+    split_policy: SplitPolicy,
     link: String,
+    status: Status,
+    status_history: Vec<Transition>,
 }
 
 impl Default for Entry {
     fn default() -> Self {
-        Entry::new(String::from("Enter name here"), 0, 0, 0.0, 0.0, 0.0, 0.0, 0.0, String::new())
+        Entry::new(String::from("Enter name here"), 0, 0, Money::ZERO, Money::ZERO, Money::ZERO, Money::ZERO, Money::ZERO, String::new())
     }
 }
 
 impl Entry {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(name: String,
         beds: i8,
         baths: i8, 
-        deposit: f32, 
-        pet_deposit: f32,
-        pet_monthly: f32,
-        parking_monthly: f32,
-        monthly_rent: f32,
+        deposit: Money, 
+        pet_deposit: Money,
+        pet_monthly: Money,
+        parking_monthly: Money,
+        monthly_rent: Money,
         link: String,) -> Self {
             let mut object = Entry {
+                id: 0,
                 name,
                 beds,
                 baths,
                 deposit,
                 pet_deposit,
+                pet_deposit_per_pet: false,
+                pet_fee: Money::ZERO,
+                move_in_fees: Money::ZERO,
                 pet_monthly,
+                parking_spots: 1,
                 parking_monthly,
                 monthly_rent,
-                total_rent: 0.0,
-                rent_for_2: 0.0,
-                rent_for_3: 0.0,
-                rent_for_4: 0.0,
-                link
+                lease_months: 12,
+                deposit_refund: 100.0,
+                concessions: Vec::new(),
+                line_items: Vec::new(),
+                effective_rent: Money::ZERO,
+                recurring_fees: Money::ZERO,
+                total_rent: Money::ZERO,
+                move_in_total: Money::ZERO,
+                lease_cost: Money::ZERO,
+                splits: Vec::new(),
+                deposit_splits: Vec::new(),
+                move_in_splits: Vec::new(),
+                split_policy: SplitPolicy::Equal,
+                link,
+                status: Status::default(),
+                status_history: Vec::new(),
             };
-            object.calculate(0);
+            object.calculate(&[Roommate::default()]);
             object
     }
 
-    pub fn calculate(&mut self, pet_count: i8) {
-        self.total_rent = self.monthly_rent + (self.pet_monthly * pet_count as f32) + self.parking_monthly;
-        self.rent_for_2 = self.total_rent/2.0 + self.parking_monthly;
-        self.rent_for_3 = self.total_rent/3.0 + self.parking_monthly * 2.0;
-        self.rent_for_4 = self.total_rent/4.0 + self.parking_monthly * 3.0;
+    // Rent and recurring line items are shared according to the split policy.
+    // Parking is priced per spot and charged only to the roommates who get one, in roster order.
+    // Pet rent, pet deposits and pet fees are charged only to pet owners, in proportion to their pets.
+    // Move-in covers the deposits, the first month and every one-time fee.
+    // The lease cost is every month of the lease plus the one-time fees and whatever part of the deposits is not expected back,
+    // less any concessions. Effective rent spreads the rent concessions over the lease.
+    pub fn calculate(&mut self, roommates: &[Roommate]) {
+        let people = roommates.len().max(1);
+        let pets: Vec<i64> = (0..people).map(|index| roommates.get(index).map(|roommate| roommate.pets.max(0) as i64).unwrap_or(0)).collect();
+        let pet_weights: Vec<f64> = pets.iter().map(|count| *count as f64).collect();
+        let total_pets: i64 = pets.iter().sum();
+
+        let mut spots_left = self.parking_spots.max(0) as usize;
+        let mut parking: Vec<Money> = vec![Money::ZERO; people];
+        for (index, roommate) in roommates.iter().enumerate() {
+            if roommate.needs_parking && spots_left > 0 {
+                parking[index] = self.parking_monthly;
+                spots_left -= 1;
+            }
+        }
+
+        self.recurring_fees = self.line_items.iter().map(|item| item.amount).sum();
+        let shared = self.monthly_rent + self.recurring_fees;
+
+        let pet_rent: Vec<Money> = pets.iter().map(|count| self.pet_monthly.times(*count)).collect();
+        self.total_rent = shared + pet_rent.iter().sum() + parking.iter().sum();
+        self.splits = self.split_policy.allocate(shared, people)
+            .iter()
+            .enumerate()
+            .map(|(index, share)| *share + pet_rent[index] + parking[index])
+            .collect();
+
+        let pet_deposit = if self.pet_deposit_per_pet { self.pet_deposit.times(total_pets) }
+            else if total_pets > 0 { self.pet_deposit }
+            else { Money::ZERO };
+        let pet_fees = self.pet_fee.times(total_pets);
+        let months = self.lease_months.max(0) as i64;
+        let rent_concessions: Money = self.concessions.iter().map(|concession| concession.rent_value(self.monthly_rent)).sum();
+        let fee_concessions: Money = self.concessions.iter().map(|concession| concession.fee_value()).sum::<Money>().min(pet_fees + self.move_in_fees);
+        // A waiver comes off the shared fees first, only what's left of it comes off the pet owners' fees
+        let shared_waiver = fee_concessions.min(self.move_in_fees);
+        let pet_fees_owed = pet_fees - (fee_concessions - shared_waiver);
+        let pet_deposit_shares = if total_pets > 0 { pet_deposit.split(&pet_weights) } else { vec![Money::ZERO; people] };
+        let pet_fee_shares = if total_pets > 0 { pet_fees_owed.split(&pet_weights) } else { vec![Money::ZERO; people] };
+
+        self.deposit_splits = self.split_policy.allocate(self.deposit, people)
+            .iter()
+            .enumerate()
+            .map(|(index, share)| *share + pet_deposit_shares[index])
+            .collect();
+
+        self.move_in_total = self.deposit + pet_deposit + self.total_rent + pet_fees + self.move_in_fees - fee_concessions;
+
+        let deposits = self.deposit + pet_deposit;
+        let expected_refund = deposits.scale(self.deposit_refund.clamp(0.0, 100.0) as f64 / 100.0);
+        self.lease_cost = self.total_rent.times(months) + pet_fees + self.move_in_fees + deposits - expected_refund - rent_concessions - fee_concessions;
+        // Free time longer than the lease makes the rent free, not negative
+        self.effective_rent = if months > 0 { (self.monthly_rent.times(months) - rent_concessions).max(Money::ZERO).divide(months) } else { self.monthly_rent };
+        self.move_in_splits = self.split_policy.allocate(self.move_in_fees - shared_waiver, people)
+            .iter()
+            .enumerate()
+            .map(|(index, share)| *share + self.deposit_splits[index] + self.splits[index] + pet_fee_shares[index])
+            .collect();
     }
 
     pub fn cmp(&self, other: &Entry, sort_field : Fields) -> Ordering {
         match sort_field {
-            Fields::Name => {
-                let result = self.name.to_ascii_lowercase().cmp(&other.name.to_ascii_lowercase());
-                return result;
+            Fields::Name => self.name.to_ascii_lowercase().cmp(&other.name.to_ascii_lowercase()),
+            Fields::Beds => self.beds.cmp(&other.beds),
+            Fields::Baths => self.baths.cmp(&other.baths),
+            Fields::Deposit => self.deposit.cmp(&other.deposit),
+            Fields::PetDeposit => self.pet_deposit.cmp(&other.pet_deposit),
+            Fields::PetMonthly => self.pet_monthly.cmp(&other.pet_monthly),
+            Fields::ParkingSpots => self.parking_spots.cmp(&other.parking_spots),
+            Fields::ParkingMonthly => self.parking_monthly.cmp(&other.parking_monthly),
+            Fields::MonthlyRent => self.monthly_rent.cmp(&other.monthly_rent),
+            Fields::EffectiveRent => self.effective_rent.cmp(&other.effective_rent),
+            Fields::RecurringFees => self.recurring_fees.cmp(&other.recurring_fees),
+            Fields::TotalRent => self.total_rent.cmp(&other.total_rent),
+            Fields::PetFee => self.pet_fee.cmp(&other.pet_fee),
+            Fields::MoveInFees => self.move_in_fees.cmp(&other.move_in_fees),
+            Fields::MoveInTotal => self.move_in_total.cmp(&other.move_in_total),
+            Fields::LeaseMonths => self.lease_months.cmp(&other.lease_months),
+            Fields::DepositRefund => self.deposit_refund.total_cmp(&other.deposit_refund),
+            Fields::LeaseCost => self.lease_cost.cmp(&other.lease_cost),
+            Fields::Link => self.link.to_ascii_lowercase().cmp(&other.link.to_ascii_lowercase()),
+            Fields::Status => self.status.cmp(&other.status),
+            Fields::PetDepositPerPet => self.pet_deposit_per_pet.cmp(&other.pet_deposit_per_pet),
+            Fields::Split(index) => {
+                let own = self.splits.get(index).copied().unwrap_or_default();
+                own.cmp(&other.splits.get(index).copied().unwrap_or_default())
             },
-            Fields::Beds => { return self.beds.cmp(&other.beds); },
-            Fields::Baths => { return self.baths.cmp(&other.baths); },
-            Fields::Deposit => { return self.deposit.total_cmp(&other.deposit); },
-            Fields::PetDeposit => { return self.pet_deposit.total_cmp(&other.pet_deposit); },
-            Fields::PetMonthly => { return self.pet_monthly.total_cmp(&other.pet_monthly); },
-            Fields::ParkingMonthly => { return self.parking_monthly.total_cmp(&other.parking_monthly); },
-            Fields::MonthlyRent => { return self.monthly_rent.total_cmp(&other.monthly_rent); },
-            Fields::TotalRent => { return self.total_rent.total_cmp(&other.total_rent); },
-            Fields::RentFor2 => { return self.rent_for_2.total_cmp(&other.rent_for_2); },
-            Fields::RentFor3 => { return self.rent_for_3.total_cmp(&other.rent_for_3); },
-            Fields::RentFor4 => { return self.rent_for_4.total_cmp(&other.rent_for_4); },
         }
     }
 
+    pub fn get_id(&self) -> u64 {
+        self.id
+    }
+
     pub fn get_name(&self) -> String {
-        return self.name.clone();
+        self.name.clone()
     }
 
     pub fn get_link(&self) -> String {
-        return self.link.clone();
+        self.link.clone()
+    }
+
+    pub fn get_deposit_splits(&self) -> Vec<Money> {
+        self.deposit_splits.clone()
+    }
+
+    pub fn get_move_in_splits(&self) -> Vec<Money> {
+        self.move_in_splits.clone()
+    }
+
+    pub fn is_pet_deposit_per_pet(&self) -> bool {
+        self.pet_deposit_per_pet
+    }
+
+    pub fn get_concessions(&self) -> Vec<Concession> {
+        self.concessions.clone()
+    }
+
+    pub fn get_line_items(&self) -> Vec<LineItem> {
+        self.line_items.clone()
+    }
+
+    pub fn get_split_policy(&self) -> SplitPolicy {
+        self.split_policy.clone()
+    }
+
+    pub fn get_status(&self) -> Status {
+        self.status
+    }
+
+    pub fn get_status_history(&self) -> Vec<Transition> {
+        self.status_history.clone()
+    }
+
+    pub fn set_id(&mut self, new_id: u64) {
+        self.id = new_id;
     }
 
     pub fn set_name(&mut self, new_name: String) {
@@ -97,8 +244,51 @@ impl Entry {
         self.link = new_link;
     }
 
-    pub fn is(&self, name: &String) -> bool {
-        return self.name.to_ascii_lowercase().cmp(&name.to_ascii_lowercase()).is_eq();
+    pub fn set_pet_deposit_per_pet(&mut self, per_pet: bool) {
+        self.pet_deposit_per_pet = per_pet;
+    }
+
+    pub fn set_concessions(&mut self, new_concessions: Vec<Concession>) {
+        self.concessions = new_concessions;
+    }
+
+    pub fn set_line_items(&mut self, new_line_items: Vec<LineItem>) {
+        self.line_items = new_line_items;
+    }
+
+    pub fn set_split_policy(&mut self, new_policy: SplitPolicy) {
+        self.split_policy = new_policy;
+    }
+
+    // Moving to a different status is recorded with the time it happened
+    pub fn set_status(&mut self, new_status: Status, at: u64) {
+        if new_status == self.status { return; }
+        self.status = new_status;
+        self.status_history.push(Transition { status: new_status, at });
+    }
+
+    // A new listing's history starts with the status it was created in, unless setting one already started it
+    pub fn start_status(&mut self, at: u64) {
+        if self.status_history.is_empty() {
+            self.status_history.push(Transition { status: self.status, at });
+        }
+    }
+
+    // For loading, where the history is already known
+    pub fn restore_status(&mut self, status: Status, history: Vec<Transition>) {
+        self.status = status;
+        self.status_history = history;
+    }
+
+    pub fn is(&self, id: u64) -> bool {
+        self.id == id
+    }
+
+    // Any numeric field as a plain number, for filtering
+    pub fn get_number(&self, field: Fields) -> Option<f64> {
+        if let Some(value) = self.get_i8(field) { return Some(value as f64); }
+        if let Some(value) = self.get_money(field) { return Some(value.as_f64()); }
+        self.get_f32(field).map(|value| value as f64)
     }
 
     pub fn get_i8(&self, field: Fields) -> Option<i8> {
@@ -109,29 +299,76 @@ impl Entry {
             Fields::Deposit => { None },
             Fields::PetDeposit => { None },
             Fields::PetMonthly => { None },
+            Fields::ParkingSpots => { Some(self.parking_spots) },
             Fields::ParkingMonthly => { None },
             Fields::MonthlyRent => { None },
+            Fields::EffectiveRent => { None },
+            Fields::RecurringFees => { None },
             Fields::TotalRent => { None },
-            Fields::RentFor2 => { None },
-            Fields::RentFor3 => { None },
-            Fields::RentFor4 => { None },
+            Fields::PetFee => { None },
+            Fields::MoveInFees => { None },
+            Fields::MoveInTotal => { None },
+            Fields::LeaseMonths => { Some(self.lease_months) },
+            Fields::DepositRefund => { None },
+            Fields::LeaseCost => { None },
+            Fields::Link => { None },
+            Fields::Status => { None },
+            Fields::PetDepositPerPet => { None },
+            Fields::Split(_) => { None },
         }
     }
 
-    pub fn get_f32(&self, field: Fields) -> Option<f32> {
+    pub fn get_money(&self, field: Fields) -> Option<Money> {
         match field {
-            Fields::Name => { None},
+            Fields::Name => { None },
             Fields::Beds => { None },
             Fields::Baths => { None },
             Fields::Deposit => { Some(self.deposit) },
             Fields::PetDeposit => { Some(self.pet_deposit) },
             Fields::PetMonthly => { Some(self.pet_monthly) },
+            Fields::ParkingSpots => { None },
             Fields::ParkingMonthly => { Some(self.parking_monthly) },
             Fields::MonthlyRent => { Some(self.monthly_rent) },
+            Fields::EffectiveRent => { Some(self.effective_rent) },
+            Fields::RecurringFees => { Some(self.recurring_fees) },
             Fields::TotalRent => { Some(self.total_rent) },
-            Fields::RentFor2 => { Some(self.rent_for_2) },
-            Fields::RentFor3 => { Some(self.rent_for_3) },
-            Fields::RentFor4 => { Some(self.rent_for_4) },
+            Fields::PetFee => { Some(self.pet_fee) },
+            Fields::MoveInFees => { Some(self.move_in_fees) },
+            Fields::MoveInTotal => { Some(self.move_in_total) },
+            Fields::LeaseMonths => { None },
+            Fields::DepositRefund => { None },
+            Fields::LeaseCost => { Some(self.lease_cost) },
+            Fields::Link => { None },
+            Fields::Status => { None },
+            Fields::PetDepositPerPet => { None },
+            Fields::Split(index) => { self.splits.get(index).copied() },
+        }
+    }
+
+    pub fn get_f32(&self, field: Fields) -> Option<f32> {
+        match field {
+            Fields::Name => { None },
+            Fields::Beds => { None },
+            Fields::Baths => { None },
+            Fields::Deposit => { None },
+            Fields::PetDeposit => { None },
+            Fields::PetMonthly => { None },
+            Fields::ParkingSpots => { None },
+            Fields::ParkingMonthly => { None },
+            Fields::MonthlyRent => { None },
+            Fields::EffectiveRent => { None },
+            Fields::RecurringFees => { None },
+            Fields::TotalRent => { None },
+            Fields::PetFee => { None },
+            Fields::MoveInFees => { None },
+            Fields::MoveInTotal => { None },
+            Fields::LeaseMonths => { None },
+            Fields::DepositRefund => { Some(self.deposit_refund) },
+            Fields::LeaseCost => { None },
+            Fields::Link => { None },
+            Fields::Status => { None },
+            Fields::PetDepositPerPet => { None },
+            Fields::Split(_) => { None },
         }
     }
 
@@ -143,16 +380,26 @@ impl Entry {
             Fields::Deposit => {},
             Fields::PetDeposit => {},
             Fields::PetMonthly => {},
+            Fields::ParkingSpots => { self.parking_spots = new_value; },
             Fields::ParkingMonthly => {},
             Fields::MonthlyRent => {},
+            Fields::EffectiveRent => {},
+            Fields::RecurringFees => {},
             Fields::TotalRent => {},
-            Fields::RentFor2 => {},
-            Fields::RentFor3 => {},
-            Fields::RentFor4 => {},
+            Fields::PetFee => {},
+            Fields::MoveInFees => {},
+            Fields::MoveInTotal => {},
+            Fields::LeaseMonths => { self.lease_months = new_value; },
+            Fields::DepositRefund => {},
+            Fields::LeaseCost => {},
+            Fields::Link => {},
+            Fields::Status => {},
+            Fields::PetDepositPerPet => {},
+            Fields::Split(_) => {},
         }
     }
 
-    pub fn set_f32(&mut self, field: Fields, new_value: f32) {
+    pub fn set_money(&mut self, field: Fields, new_value: Money) {
         match field {
             Fields::Name => {},
             Fields::Beds => {},
@@ -160,12 +407,276 @@ impl Entry {
             Fields::Deposit => {self.deposit = new_value; },
             Fields::PetDeposit => {self.pet_deposit = new_value;},
             Fields::PetMonthly => {self.pet_monthly = new_value;},
+            Fields::ParkingSpots => {},
             Fields::ParkingMonthly => {self.parking_monthly = new_value;},
             Fields::MonthlyRent => {self.monthly_rent = new_value;},
+            Fields::EffectiveRent => {},
+            Fields::RecurringFees => {},
             Fields::TotalRent => {},
-            Fields::RentFor2 => {},
-            Fields::RentFor3 => {},
-            Fields::RentFor4 => {},
+            Fields::PetFee => {self.pet_fee = new_value;},
+            Fields::MoveInFees => {self.move_in_fees = new_value;},
+            Fields::MoveInTotal => {},
+            Fields::LeaseMonths => {},
+            Fields::DepositRefund => {},
+            Fields::LeaseCost => {},
+            Fields::Link => {},
+            Fields::Status => {},
+            Fields::PetDepositPerPet => {},
+            Fields::Split(_) => {},
         }
     }
-}
\ No newline at end of file
+
+    pub fn set_f32(&mut self, field: Fields, new_value: f32) {
+        match field {
+            Fields::Name => {},
+            Fields::Beds => {},
+            Fields::Baths => {},
+            Fields::Deposit => {},
+            Fields::PetDeposit => {},
+            Fields::PetMonthly => {},
+            Fields::ParkingSpots => {},
+            Fields::ParkingMonthly => {},
+            Fields::MonthlyRent => {},
+            Fields::EffectiveRent => {},
+            Fields::RecurringFees => {},
+            Fields::TotalRent => {},
+            Fields::PetFee => {},
+            Fields::MoveInFees => {},
+            Fields::MoveInTotal => {},
+            Fields::LeaseMonths => {},
+            Fields::DepositRefund => {self.deposit_refund = new_value;},
+            Fields::LeaseCost => {},
+            Fields::Link => {},
+            Fields::Status => {},
+            Fields::PetDepositPerPet => {},
+            Fields::Split(_) => {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::concession::ConcessionKind;
+    use crate::test_support::with_rent;
+
+    fn household(parking: &[bool]) -> Vec<Roommate> {
+        parking.iter().map(|needs_parking| Roommate { needs_parking: *needs_parking, ..Default::default() }).collect()
+    }
+
+    fn with_pets(pets: &[i8]) -> Vec<Roommate> {
+        pets.iter().map(|pets| Roommate { pets: *pets, ..Default::default() }).collect()
+    }
+
+    fn dollars(amount: i64) -> Money {
+        Money::from_cents(amount * 100)
+    }
+
+    fn listing(parking_spots: i8, parking_monthly: i64) -> Entry {
+        let mut entry = with_rent("Test", 90_000);
+        entry.set_money(Fields::Deposit, dollars(600));
+        entry.set_money(Fields::PetDeposit, dollars(300));
+        entry.set_money(Fields::PetMonthly, dollars(20));
+        entry.set_money(Fields::ParkingMonthly, dollars(parking_monthly));
+        entry.set_i8(Fields::ParkingSpots, parking_spots);
+        entry
+    }
+
+    #[test]
+    fn rent_splits_across_any_number_of_roommates() {
+        for people in 1..=5 {
+            let mut entry = listing(0, 0);
+            entry.calculate(&vec![Roommate::default(); people]);
+
+            let splits: Vec<Money> = (0..people).map(|index| entry.get_money(Fields::Split(index)).unwrap()).collect();
+            assert_eq!(splits.iter().copied().sum::<Money>(), entry.get_money(Fields::TotalRent).unwrap());
+            assert_eq!(entry.get_money(Fields::Split(people)), None);
+        }
+    }
+
+    #[test]
+    fn parking_is_charged_only_to_roommates_who_need_it() {
+        let mut entry = listing(2, 50);
+        entry.calculate(&household(&[true, false, true]));
+
+        assert_eq!(entry.get_money(Fields::TotalRent), Some(dollars(1000)));
+        assert_eq!(entry.get_money(Fields::Split(0)), Some(dollars(350)));
+        assert_eq!(entry.get_money(Fields::Split(1)), Some(dollars(300)));
+        assert_eq!(entry.get_money(Fields::Split(2)), Some(dollars(350)));
+    }
+
+    #[test]
+    fn parking_is_capped_by_available_spots() {
+        let mut entry = listing(1, 50);
+        entry.calculate(&household(&[false, true, true]));
+
+        assert_eq!(entry.get_money(Fields::TotalRent), Some(dollars(950)));
+        assert_eq!(entry.get_money(Fields::Split(0)), Some(dollars(300)));
+        assert_eq!(entry.get_money(Fields::Split(1)), Some(dollars(350)));
+        assert_eq!(entry.get_money(Fields::Split(2)), Some(dollars(300)));
+    }
+
+    #[test]
+    fn no_parking_needed_means_no_parking_charged() {
+        let mut entry = listing(2, 50);
+        entry.calculate(&household(&[false, false]));
+
+        assert_eq!(entry.get_money(Fields::TotalRent), Some(dollars(900)));
+        assert_eq!(entry.get_money(Fields::Split(0)), Some(dollars(450)));
+        assert_eq!(entry.get_money(Fields::Split(1)), Some(dollars(450)));
+    }
+
+    #[test]
+    fn shared_costs_exclude_parking() {
+        let mut entry = listing(1, 100);
+        entry.calculate(&household(&[true, false]));
+
+        assert_eq!(entry.get_money(Fields::TotalRent), Some(dollars(1000)));
+        assert_eq!(entry.get_money(Fields::Split(0)), Some(dollars(550)));
+        assert_eq!(entry.get_money(Fields::Split(1)), Some(dollars(450)));
+    }
+
+    #[test]
+    fn pet_fees_are_charged_only_to_pet_owners() {
+        let mut entry = listing(0, 0);
+        entry.calculate(&with_pets(&[2, 0, 1]));
+
+        assert_eq!(entry.get_money(Fields::TotalRent), Some(dollars(960)));
+        assert_eq!(entry.get_money(Fields::Split(0)), Some(dollars(340)));
+        assert_eq!(entry.get_money(Fields::Split(1)), Some(dollars(300)));
+        assert_eq!(entry.get_money(Fields::Split(2)), Some(dollars(320)));
+        assert_eq!(entry.get_deposit_splits(), vec![dollars(400), dollars(200), dollars(300)]);
+    }
+
+    #[test]
+    fn pet_deposit_can_scale_with_pet_count() {
+        let mut entry = listing(0, 0);
+        entry.set_pet_deposit_per_pet(true);
+        entry.calculate(&with_pets(&[2, 0, 1]));
+
+        assert_eq!(entry.get_deposit_splits(), vec![dollars(800), dollars(200), dollars(500)]);
+    }
+
+    #[test]
+    fn move_in_total_covers_deposits_first_month_and_fees() {
+        let mut entry = listing(0, 0);
+        entry.set_pet_deposit_per_pet(true);
+        entry.set_money(Fields::PetFee, dollars(150));
+        entry.set_money(Fields::MoveInFees, dollars(90));
+        entry.calculate(&with_pets(&[2, 0, 1]));
+
+        assert_eq!(entry.get_money(Fields::MoveInTotal), Some(dollars(600 + 900 + 960 + 450 + 90)));
+        assert_eq!(entry.get_move_in_splits(), vec![dollars(30 + 800 + 340 + 300), dollars(30 + 200 + 300), dollars(30 + 500 + 320 + 150)]);
+    }
+
+    #[test]
+    fn lease_cost_counts_every_month_and_lost_deposit() {
+        let mut entry = listing(0, 0);
+        entry.set_money(Fields::PetFee, dollars(150));
+        entry.set_money(Fields::MoveInFees, dollars(90));
+        entry.set_i8(Fields::LeaseMonths, 6);
+        entry.set_f32(Fields::DepositRefund, 75.0);
+        entry.calculate(&with_pets(&[1, 0]));
+
+        assert_eq!(entry.get_money(Fields::LeaseCost), Some(dollars(920 * 6 + 150 + 90 + 225)));
+    }
+
+    #[test]
+    fn concessions_lower_effective_rent_and_lease_cost() {
+        let mut entry = listing(0, 0);
+        entry.set_money(Fields::MoveInFees, dollars(90));
+        entry.set_concessions(vec![
+            Concession { kind: ConcessionKind::FreeMonths, count: 1, ..Concession::default() },
+            Concession { kind: ConcessionKind::Credit, amount: dollars(300), ..Concession::default() },
+            Concession { kind: ConcessionKind::WaivedFee, amount: dollars(200), ..Concession::default() },
+        ]);
+        entry.calculate(&with_pets(&[0, 0]));
+
+        assert_eq!(entry.get_money(Fields::EffectiveRent), Some(dollars(800)));
+        assert_eq!(entry.get_money(Fields::LeaseCost), Some(dollars(900 * 12 - 1200)));
+        assert_eq!(entry.get_money(Fields::MoveInTotal), Some(dollars(600 + 900)));
+    }
+
+    #[test]
+    fn waived_pet_fees_only_lower_the_owners_move_in() {
+        let mut entry = listing(0, 0);
+        entry.set_money(Fields::PetFee, dollars(150));
+        entry.calculate(&with_pets(&[1, 0]));
+        let [owner, other] = entry.get_move_in_splits()[..] else { panic!("expected two shares") };
+
+        entry.set_concessions(vec![Concession { kind: ConcessionKind::WaivedFee, amount: dollars(150), ..Concession::default() }]);
+        entry.calculate(&with_pets(&[1, 0]));
+
+        assert_eq!(entry.get_move_in_splits(), vec![owner - dollars(150), other]);
+        assert_eq!(entry.get_move_in_splits().iter().copied().sum::<Money>(), entry.get_money(Fields::MoveInTotal).unwrap());
+    }
+
+    #[test]
+    fn shared_fees_are_waived_before_pet_fees() {
+        let mut entry = listing(0, 0);
+        entry.set_money(Fields::PetFee, dollars(150));
+        entry.set_money(Fields::MoveInFees, dollars(100));
+        entry.calculate(&with_pets(&[1, 0]));
+        let [owner, other] = entry.get_move_in_splits()[..] else { panic!("expected two shares") };
+
+        entry.set_concessions(vec![Concession { kind: ConcessionKind::WaivedFee, amount: dollars(130), ..Concession::default() }]);
+        entry.calculate(&with_pets(&[1, 0]));
+
+        assert_eq!(entry.get_move_in_splits(), vec![owner - dollars(50 + 30), other - dollars(50)]);
+    }
+
+    #[test]
+    fn free_time_longer_than_the_lease_makes_rent_free() {
+        let mut entry = listing(0, 0);
+        entry.set_i8(Fields::LeaseMonths, 2);
+        entry.set_concessions(vec![Concession { kind: ConcessionKind::FreeMonths, count: 3, ..Concession::default() }]);
+        entry.calculate(&with_pets(&[0, 0]));
+
+        assert_eq!(entry.get_money(Fields::EffectiveRent), Some(Money::ZERO));
+    }
+
+    #[test]
+    fn line_items_are_shared_like_rent() {
+        let mut entry = listing(0, 0);
+        entry.set_line_items(vec![
+            LineItem { name: String::from("Trash"), amount: dollars(15) },
+            LineItem { name: String::from("Internet"), amount: dollars(75) },
+        ]);
+        entry.calculate(&with_pets(&[1, 0]));
+
+        assert_eq!(entry.get_money(Fields::RecurringFees), Some(dollars(90)));
+        assert_eq!(entry.get_money(Fields::TotalRent), Some(dollars(1010)));
+        assert_eq!(entry.get_money(Fields::Split(0)), Some(dollars(515)));
+        assert_eq!(entry.get_money(Fields::Split(1)), Some(dollars(495)));
+    }
+
+    #[test]
+    fn uneven_splits_still_add_up_to_the_total() {
+        let mut entry = listing(0, 0);
+        entry.set_money(Fields::MonthlyRent, dollars(1000));
+        entry.calculate(&with_pets(&[0, 0, 0]));
+
+        assert_eq!(entry.get_money(Fields::Split(0)), Some(Money::from_cents(33334)));
+        assert_eq!(entry.get_money(Fields::Split(1)), Some(Money::from_cents(33333)));
+        assert_eq!(entry.get_money(Fields::Split(2)), Some(Money::from_cents(33333)));
+    }
+
+    #[test]
+    fn pet_deposit_is_not_charged_without_pets() {
+        let mut entry = listing(0, 0);
+        entry.calculate(&with_pets(&[0, 0]));
+
+        assert_eq!(entry.get_deposit_splits(), vec![dollars(300), dollars(300)]);
+    }
+
+    #[test]
+    fn status_changes_are_timestamped() {
+        let mut entry = listing(0, 0);
+        entry.set_status(Status::Toured, 100);
+        entry.set_status(Status::Toured, 200);
+        entry.set_status(Status::Applied, 300);
+
+        assert_eq!(entry.get_status(), Status::Applied);
+        assert_eq!(entry.get_status_history(), vec![Transition { status: Status::Toured, at: 100 }, Transition { status: Status::Applied, at: 300 }]);
+    }
+}
@@ -1,15 +1,132 @@
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Fields {
     Name,
     Beds,
     Baths,
     Deposit,
     PetDeposit,
+    PetDepositPerPet,
     PetMonthly,
+    ParkingSpots,
     ParkingMonthly,
     MonthlyRent,
+    EffectiveRent,
+    RecurringFees,
     TotalRent,
-    RentFor2,
-    RentFor3,
-    RentFor4,
-}
\ No newline at end of file
+    PetFee,
+    MoveInFees,
+    MoveInTotal,
+    LeaseMonths,
+    DepositRefund,
+    LeaseCost,
+    Link,
+    Status,
+    Split(usize),
+}
+
+impl Fields {
+    pub const NUMERIC: [Fields; 17] = [
+        Fields::Beds,
+        Fields::Baths,
+        Fields::Deposit,
+        Fields::PetDeposit,
+        Fields::PetFee,
+        Fields::MoveInFees,
+        Fields::PetMonthly,
+        Fields::ParkingSpots,
+        Fields::ParkingMonthly,
+        Fields::MonthlyRent,
+        Fields::RecurringFees,
+        Fields::EffectiveRent,
+        Fields::LeaseMonths,
+        Fields::DepositRefund,
+        Fields::TotalRent,
+        Fields::MoveInTotal,
+        Fields::LeaseCost,
+    ];
+
+    // What a person types in, as opposed to what calculate works out
+    pub const INPUTS: [Fields; 16] = [
+        Fields::Name,
+        Fields::Beds,
+        Fields::Baths,
+        Fields::Deposit,
+        Fields::PetDeposit,
+        Fields::PetDepositPerPet,
+        Fields::PetFee,
+        Fields::MoveInFees,
+        Fields::PetMonthly,
+        Fields::ParkingSpots,
+        Fields::ParkingMonthly,
+        Fields::MonthlyRent,
+        Fields::LeaseMonths,
+        Fields::DepositRefund,
+        Fields::Link,
+        Fields::Status,
+    ];
+
+    pub fn key(&self) -> String {
+        let key = match self {
+            Fields::Name => "name",
+            Fields::Beds => "beds",
+            Fields::Baths => "baths",
+            Fields::Deposit => "deposit",
+            Fields::PetDeposit => "petdeposit",
+            Fields::PetDepositPerPet => "petdepositperpet",
+            Fields::PetMonthly => "petmonthly",
+            Fields::ParkingSpots => "parkingspots",
+            Fields::ParkingMonthly => "parkingmonthly",
+            Fields::MonthlyRent => "monthlyrent",
+            Fields::EffectiveRent => "effectiverent",
+            Fields::RecurringFees => "recurringfees",
+            Fields::TotalRent => "totalrent",
+            Fields::PetFee => "petfee",
+            Fields::MoveInFees => "moveinfees",
+            Fields::MoveInTotal => "moveintotal",
+            Fields::LeaseMonths => "leasemonths",
+            Fields::DepositRefund => "depositrefund",
+            Fields::LeaseCost => "leasecost",
+            Fields::Link => "link",
+            Fields::Status => "status",
+            Fields::Split(index) => { return format!("split{}", index); },
+        };
+        key.to_string()
+    }
+
+    pub fn from_key(key: &str) -> Option<Fields> {
+        if let Some(index) = key.strip_prefix("split") {
+            return index.parse::<usize>().ok().map(Fields::Split);
+        }
+        [Fields::Name, Fields::Link, Fields::Status].into_iter()
+            .chain(Fields::NUMERIC)
+            .find(|field| field.key() == key)
+    }
+
+    pub fn label(&self) -> String {
+        let label = match self {
+            Fields::Name => "Name",
+            Fields::Beds => "Number of Beds",
+            Fields::Baths => "Number of Baths",
+            Fields::Deposit => "Deposit",
+            Fields::PetDeposit => "Pet Deposit",
+            Fields::PetDepositPerPet => "Pet Deposit Per Pet",
+            Fields::PetMonthly => "Pet Monthly",
+            Fields::ParkingSpots => "Parking Spots",
+            Fields::ParkingMonthly => "Parking per Spot",
+            Fields::MonthlyRent => "Monthly Rent",
+            Fields::EffectiveRent => "Effective Rent",
+            Fields::RecurringFees => "Recurring Fees",
+            Fields::TotalRent => "Total Rent",
+            Fields::PetFee => "Pet Fee",
+            Fields::MoveInFees => "Move-in Fees",
+            Fields::MoveInTotal => "Move-in Total",
+            Fields::LeaseMonths => "Lease Months",
+            Fields::DepositRefund => "Deposit Refund %",
+            Fields::LeaseCost => "Lease Cost",
+            Fields::Link => "Link",
+            Fields::Status => "Status",
+            Fields::Split(index) => { return format!("Roommate {}", index + 1); },
+        };
+        label.to_string()
+    }
+}
@@ -0,0 +1,132 @@
+use crate::objects::entry::Entry;
+use crate::objects::fields::Fields;
+use crate::objects::status::Status;
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct RangeFilter {
+    pub field: Fields,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Filters {
+    pub name: String,
+    pub link: String,
+    pub ranges: Vec<RangeFilter>,
+    pub statuses: Vec<Status>,
+}
+
+impl Default for Filters {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            link: String::new(),
+            ranges: Vec::new(),
+            statuses: Status::ACTIVE.to_vec(),
+        }
+    }
+}
+
+impl RangeFilter {
+    pub fn matches(&self, entry: &Entry) -> bool {
+        let Some(value) = entry.get_number(self.field) else { return true; };
+
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+}
+
+impl Filters {
+    // Every filter that is filled in has to match
+    pub fn matches(&self, entry: &Entry) -> bool {
+        contains_ignore_case(&entry.get_name(), &self.name)
+            && contains_ignore_case(&entry.get_link(), &self.link)
+            && self.ranges.iter().all(|range| range.matches(entry))
+            && self.statuses.contains(&entry.get_status())
+    }
+
+    // Hiding archived listings is the default, so it doesn't count as filtering
+    pub fn is_empty(&self) -> bool {
+        self.name.is_empty() && self.link.is_empty() && self.ranges.is_empty() && self.statuses == Status::ACTIVE
+    }
+
+    pub fn shows(&self, status: Status) -> bool {
+        self.statuses.contains(&status)
+    }
+
+    // Keeps the statuses in their usual order, so filters that show the same ones compare equal
+    pub fn set_shown(&mut self, status: Status, shown: bool) {
+        self.statuses.retain(|existing| *existing != status);
+        if shown {
+            self.statuses.push(status);
+            self.statuses.sort();
+        }
+    }
+
+    pub fn get_range(&self, field: Fields) -> RangeFilter {
+        self.ranges.iter()
+            .find(|range| range.field == field)
+            .copied()
+            .unwrap_or(RangeFilter { field, min: None, max: None })
+    }
+
+    pub fn set_range(&mut self, range: RangeFilter) {
+        let active = range.min.is_some() || range.max.is_some();
+        match self.ranges.iter().position(|existing| existing.field == range.field) {
+            Some(index) if active => { self.ranges[index] = range; },
+            Some(index) => { self.ranges.remove(index); },
+            None if active => { self.ranges.push(range); },
+            None => {},
+        }
+    }
+}
+
+fn contains_ignore_case(text: &str, pattern: &str) -> bool {
+    text.to_lowercase().contains(&pattern.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::with_rent;
+
+    #[test]
+    fn ranges_include_their_bounds() {
+        let range = RangeFilter { field: Fields::MonthlyRent, min: Some(1000.0), max: Some(1500.0) };
+
+        assert!(range.matches(&with_rent("Oak", 100_000)));
+        assert!(range.matches(&with_rent("Oak", 150_000)));
+        assert!(!range.matches(&with_rent("Oak", 99_900)));
+        assert!(!range.matches(&with_rent("Oak", 150_100)));
+        let mut three_beds = with_rent("Oak", 500_000);
+        three_beds.set_i8(Fields::Beds, 3);
+        assert!(RangeFilter { field: Fields::Beds, min: Some(2.0), max: None }.matches(&three_beds));
+        // Fields without a number, like the name, never filter anything out
+        assert!(RangeFilter { field: Fields::Name, min: Some(1.0), max: Some(1.0) }.matches(&with_rent("Oak", 100_000)));
+    }
+
+    #[test]
+    fn empty_ranges_are_dropped() {
+        let mut filters = Filters::default();
+        filters.set_range(RangeFilter { field: Fields::Beds, min: Some(2.0), max: None });
+        filters.set_range(RangeFilter { field: Fields::Beds, min: Some(3.0), max: None });
+        assert_eq!(filters.ranges.len(), 1);
+        assert_eq!(filters.get_range(Fields::Beds).min, Some(3.0));
+        assert!(!filters.is_empty());
+
+        filters.set_range(RangeFilter { field: Fields::Beds, min: None, max: None });
+        filters.set_range(RangeFilter { field: Fields::Baths, min: None, max: None });
+        assert!(filters.ranges.is_empty());
+        assert!(filters.is_empty());
+    }
+
+    #[test]
+    fn every_filter_has_to_match() {
+        let mut filters = Filters { name: String::from("oak"), ..Filters::default() };
+        filters.set_range(RangeFilter { field: Fields::MonthlyRent, min: None, max: Some(1200.0) });
+
+        assert!(filters.matches(&with_rent("Oak Court", 110_000)));
+        assert!(!filters.matches(&with_rent("Oak Court", 130_000)));
+        assert!(!filters.matches(&with_rent("Elm Street", 110_000)));
+    }
+}
@@ -0,0 +1,246 @@
+use crate::objects::money::Money;
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum Currency {
+    #[default]
+    Usd,
+    Cad,
+    Eur,
+    Gbp,
+}
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum Locale {
+    #[default]
+    EnUs,
+    EnGb,
+    DeDe,
+    FrFr,
+}
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub struct MoneyFormat {
+    pub currency: Currency,
+    pub locale: Locale,
+}
+
+impl Currency {
+    pub const ALL: [Currency; 4] = [Currency::Usd, Currency::Cad, Currency::Eur, Currency::Gbp];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Currency::Usd => "USD",
+            Currency::Cad => "CAD",
+            Currency::Eur => "EUR",
+            Currency::Gbp => "GBP",
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Currency::Usd => "$",
+            Currency::Cad => "CA$",
+            Currency::Eur => "€",
+            Currency::Gbp => "£",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Currency::ALL.into_iter().find(|currency| currency.code().eq_ignore_ascii_case(code))
+    }
+}
+
+impl Locale {
+    pub const ALL: [Locale; 4] = [Locale::EnUs, Locale::EnGb, Locale::DeDe, Locale::FrFr];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::EnUs => "en-US",
+            Locale::EnGb => "en-GB",
+            Locale::DeDe => "de-DE",
+            Locale::FrFr => "fr-FR",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Locale::ALL.into_iter().find(|locale| locale.code().eq_ignore_ascii_case(code))
+    }
+
+    pub fn thousands_separator(&self) -> &'static str {
+        match self {
+            Locale::EnUs | Locale::EnGb => ",",
+            Locale::DeDe => ".",
+            Locale::FrFr => "\u{00A0}",
+        }
+    }
+
+    pub fn decimal_separator(&self) -> &'static str {
+        match self {
+            Locale::EnUs | Locale::EnGb => ".",
+            Locale::DeDe | Locale::FrFr => ",",
+        }
+    }
+
+    pub fn symbol_first(&self) -> bool {
+        matches!(self, Locale::EnUs | Locale::EnGb)
+    }
+
+    // French groups with a narrow space that nobody can type, so any space counts there
+    pub fn is_thousands_separator(&self, c: char) -> bool {
+        match self {
+            Locale::EnUs | Locale::EnGb => c == ',',
+            Locale::DeDe => c == '.',
+            Locale::FrFr => c.is_whitespace(),
+        }
+    }
+}
+
+impl MoneyFormat {
+    pub fn format(&self, amount: Money) -> String {
+        let (sign, number) = self.localize(&amount.to_string());
+        if self.locale.symbol_first() {
+            return format!("{}{}{}", sign, self.currency.symbol(), number);
+        }
+        format!("{}{} {}", sign, number, self.currency.symbol())
+    }
+
+    // For editable cells, without the symbol and with whole amounts left at "1,200"
+    pub fn format_input(&self, amount: Money) -> String {
+        let (sign, number) = self.localize(&amount.to_short_string());
+        format!("{}{}", sign, number)
+    }
+
+    fn localize(&self, plain: &str) -> (&'static str, String) {
+        let (sign, digits) = match plain.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", plain),
+        };
+
+        let mut number = String::new();
+        let (whole, cents) = digits.split_once('.').unwrap_or((digits, ""));
+        for (index, digit) in whole.chars().enumerate() {
+            if index > 0 && (whole.len() - index) % 3 == 0 {
+                number.push_str(self.locale.thousands_separator());
+            }
+            number.push(digit);
+        }
+        if !cents.is_empty() {
+            number.push_str(self.locale.decimal_separator());
+            number.push_str(cents);
+        }
+
+        (sign, number)
+    }
+
+    // Accepts what people paste from listings, e.g. "$1,200.50", "1200" or "1.200,50 €" in German
+    pub fn parse(&self, text: &str) -> Result<Money, String> {
+        let example = format!("Enter an amount like {}", self.format(Money::from_cents(120050)));
+
+        let mut text = text.trim().to_string();
+        let mut symbols: Vec<&str> = Currency::ALL.iter().map(|currency| currency.symbol()).collect();
+        symbols.sort_by_key(|symbol| std::cmp::Reverse(symbol.len()));
+        for symbol in symbols {
+            text = text.replace(symbol, "");
+        }
+
+        let text = text.trim();
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest.trim()),
+            None => (false, text),
+        };
+        let (whole, fraction) = digits.split_once(self.locale.decimal_separator()).unwrap_or((digits, ""));
+        if fraction.len() > 2 && fraction.chars().all(|c| c.is_ascii_digit()) {
+            return Err(String::from("Amounts have at most two decimal places"));
+        }
+
+        let groups: Vec<&str> = whole.split(|c| self.locale.is_thousands_separator(c)).collect();
+        if groups.len() > 1 && (groups[0].is_empty() || groups[0].len() > 3 || groups[1..].iter().any(|group| group.len() != 3)) {
+            return Err(example);
+        }
+
+        let sign = if negative { "-" } else { "" };
+        Money::parse(&format!("{}{}.{}", sign, groups.concat(), fraction)).ok_or(example)
+    }
+}
+
+pub fn parse_count(text: &str) -> Result<i8, String> {
+    match text.trim().parse::<i64>() {
+        Ok(value) if (0..=i8::MAX as i64).contains(&value) => Ok(value as i8),
+        Ok(_) => Err(format!("Enter a whole number from 0 to {}", i8::MAX)),
+        Err(_) => Err(String::from("Enter a whole number, like 2")),
+    }
+}
+
+pub fn parse_percent(text: &str) -> Result<f32, String> {
+    let text = text.trim();
+    let number = text.strip_suffix('%').unwrap_or(text).trim();
+    match number.parse::<f32>() {
+        Ok(value) if (0.0..=100.0).contains(&value) => Ok(value),
+        Ok(_) => Err(String::from("Enter a percentage from 0 to 100")),
+        Err(_) => Err(String::from("Enter a percentage, like 80 or 80%")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_with_symbol_and_grouping() {
+        let format = MoneyFormat { currency: Currency::Usd, locale: Locale::EnUs };
+
+        assert_eq!(format.format(Money::from_cents(123456789)), "$1,234,567.89");
+        assert_eq!(format.format(Money::from_cents(-51667)), "-$516.67");
+        assert_eq!(format.format(Money::from_cents(5)), "$0.05");
+    }
+
+    #[test]
+    fn formats_for_european_locales() {
+        let german = MoneyFormat { currency: Currency::Eur, locale: Locale::DeDe };
+        let french = MoneyFormat { currency: Currency::Eur, locale: Locale::FrFr };
+
+        assert_eq!(german.format(Money::from_cents(123450)), "1.234,50 €");
+        assert_eq!(french.format(Money::from_cents(123450)), "1\u{00A0}234,50 €");
+    }
+
+    #[test]
+    fn parses_pasted_amounts() {
+        let us = MoneyFormat { currency: Currency::Usd, locale: Locale::EnUs };
+        let german = MoneyFormat { currency: Currency::Eur, locale: Locale::DeDe };
+        let french = MoneyFormat { currency: Currency::Eur, locale: Locale::FrFr };
+
+        assert_eq!(us.parse("$1,200.50"), Ok(Money::from_cents(120050)));
+        assert_eq!(us.parse(" 1200 "), Ok(Money::from_cents(120000)));
+        assert_eq!(us.parse("-$5"), Ok(Money::from_cents(-500)));
+        assert_eq!(us.parse("CA$1,000"), Ok(Money::from_cents(100000)));
+        assert_eq!(german.parse("1.200,50 €"), Ok(Money::from_cents(120050)));
+        assert_eq!(french.parse("1 200,5"), Ok(Money::from_cents(120050)));
+
+        assert_eq!(us.parse("1,20.50"), Err(String::from("Enter an amount like $1,200.50")));
+        assert_eq!(german.parse("1200.50"), Err(String::from("Enter an amount like 1.200,50 €")));
+        assert_eq!(us.parse("12.505"), Err(String::from("Amounts have at most two decimal places")));
+        assert!(us.parse("twelve").is_err());
+    }
+
+    #[test]
+    fn input_text_parses_back() {
+        for locale in Locale::ALL {
+            let format = MoneyFormat { currency: Currency::Eur, locale };
+            for cents in [0, 5, 120000, 120050, -123456789] {
+                let amount = Money::from_cents(cents);
+                assert_eq!(format.parse(&format.format_input(amount)), Ok(amount));
+                assert_eq!(format.parse(&format.format(amount)), Ok(amount));
+            }
+        }
+    }
+
+    #[test]
+    fn parses_counts_and_percentages() {
+        assert_eq!(parse_count(" 3 "), Ok(3));
+        assert!(parse_count("-1").is_err());
+        assert!(parse_count("1.5").is_err());
+        assert_eq!(parse_percent("85%"), Ok(85.0));
+        assert_eq!(parse_percent("72.5"), Ok(72.5));
+        assert!(parse_percent("120").is_err());
+    }
+}
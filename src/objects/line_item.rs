@@ -0,0 +1,7 @@
+use crate::objects::money::Money;
+
+#[derive(Clone, PartialEq, Default)]
+pub struct LineItem {
+    pub name: String,
+    pub amount: Money,
+}
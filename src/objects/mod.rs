@@ -1,2 +1,11 @@
 pub mod fields;
 pub mod entry;
+pub mod split;
+pub mod roommate;
+pub mod concession;
+pub mod line_item;
+pub mod money;
+pub mod format;
+pub mod filter;
+pub mod sort;
+pub mod status;
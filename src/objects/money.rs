@@ -0,0 +1,213 @@
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+// Whole cents, so totals and splits never pick up float error
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub fn from_cents(cents: i64) -> Self {
+        Money(cents)
+    }
+
+    // Rounds half away from zero to the nearest cent
+    pub fn from_f64(dollars: f64) -> Self {
+        Money::from_cents((dollars * 100.0).round() as i64)
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        self.0 as f64 / 100.0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn times(&self, count: i64) -> Money {
+        Money(self.0 * count)
+    }
+
+    // Rounds half away from zero to the nearest cent
+    pub fn scale(&self, factor: f64) -> Money {
+        Money((self.0 as f64 * factor).round() as i64)
+    }
+
+    // Rounds half away from zero to the nearest cent
+    pub fn divide(&self, divisor: i64) -> Money {
+        if divisor == 0 { return *self; }
+
+        let quotient = self.0 / divisor;
+        let remainder = self.0 % divisor;
+        if (remainder * 2).abs() >= divisor.abs() {
+            return Money(quotient + (self.0.signum() * divisor.signum()));
+        }
+        Money(quotient)
+    }
+
+    // Splits into parts proportional to the weights that always add back up to exactly this amount.
+    // Every part is rounded down to the cent, then the leftover cents go to the parts that lost the
+    // most in rounding, earliest first on ties. Without any positive weight the split is even.
+    pub fn split(&self, weights: &[f64]) -> Vec<Money> {
+        if weights.is_empty() { return Vec::new(); }
+        if self.0 < 0 {
+            return (-*self).split(weights).into_iter().map(|part| -part).collect();
+        }
+
+        let mut weights: Vec<f64> = weights.iter().map(|weight| weight.max(0.0)).collect();
+        let mut sum: f64 = weights.iter().sum();
+        if sum <= 0.0 {
+            weights = vec![1.0; weights.len()];
+            sum = weights.len() as f64;
+        }
+
+        let exact: Vec<f64> = weights.iter().map(|weight| self.0 as f64 * weight / sum).collect();
+        let mut parts: Vec<i64> = exact.iter().map(|value| value.floor() as i64).collect();
+        let leftover = self.0 - parts.iter().sum::<i64>();
+
+        let mut order: Vec<usize> = (0..parts.len()).collect();
+        order.sort_by(|a, b| (exact[*b] - parts[*b] as f64).total_cmp(&(exact[*a] - parts[*a] as f64)));
+        for index in order.into_iter().cycle().take(leftover.max(0) as usize) {
+            parts[index] += 1;
+        }
+
+        parts.into_iter().map(Money).collect()
+    }
+
+    pub fn split_evenly(&self, people: usize) -> Vec<Money> {
+        self.split(&vec![1.0; people])
+    }
+
+    // Drops trailing zeros in the cents, so "1200.50" reads "1200.5" and "1200.00" reads "1200"
+    pub fn to_short_string(self) -> String {
+        let full = self.to_string();
+        full.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+
+    pub fn parse(text: &str) -> Option<Money> {
+        let text = text.trim();
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+
+        let (whole, fraction) = match digits.split_once('.') {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (digits, ""),
+        };
+        if whole.is_empty() && fraction.is_empty() { return None; }
+        if !whole.chars().all(|c| c.is_ascii_digit()) || !fraction.chars().all(|c| c.is_ascii_digit()) { return None; }
+        if fraction.len() > 2 { return None; }
+
+        let whole: i64 = if whole.is_empty() { 0 } else { whole.parse().ok()? };
+        let fraction: i64 = format!("{:0<2}", fraction).parse().ok()?;
+        let cents = whole.checked_mul(100)?.checked_add(fraction)?;
+
+        Some(Money(if negative { -cents } else { cents }))
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        write!(f, "{}{}.{:02}", sign, self.0.abs() / 100, self.0.abs() % 100)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money(self.0 + other.0)
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        Money(self.0 - other.0)
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        self.0 += other.0;
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, other: Money) {
+        self.0 -= other.0;
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, |total, amount| total + amount)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, |total, amount| total + *amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn even_split_hands_leftover_cents_to_the_first_people() {
+        let parts = Money::from_cents(100000).split_evenly(3);
+
+        assert_eq!(parts, vec![Money::from_cents(33334), Money::from_cents(33333), Money::from_cents(33333)]);
+        assert_eq!(parts.iter().sum::<Money>(), Money::from_cents(100000));
+    }
+
+    #[test]
+    fn weighted_split_sums_exactly() {
+        let total = Money::from_cents(100001);
+        let parts = total.split(&[150.0, 120.0, 97.5]);
+
+        assert_eq!(parts.iter().sum::<Money>(), total);
+        assert_eq!(parts, vec![Money::from_cents(40817), Money::from_cents(32653), Money::from_cents(26531)]);
+    }
+
+    #[test]
+    fn negative_amounts_split_symmetrically() {
+        let parts = Money::from_cents(-100).split_evenly(3);
+
+        assert_eq!(parts, vec![Money::from_cents(-34), Money::from_cents(-33), Money::from_cents(-33)]);
+    }
+
+    #[test]
+    fn divide_rounds_half_away_from_zero() {
+        assert_eq!(Money::from_cents(5).divide(2), Money::from_cents(3));
+        assert_eq!(Money::from_cents(-5).divide(2), Money::from_cents(-3));
+        assert_eq!(Money::from_cents(1000).divide(3), Money::from_cents(333));
+    }
+
+    #[test]
+    fn parses_and_displays_two_decimals() {
+        assert_eq!(Money::parse("1200.5"), Some(Money::from_cents(120050)));
+        assert_eq!(Money::parse("-.25"), Some(Money::from_cents(-25)));
+        assert_eq!(Money::parse("12.345"), None);
+        assert_eq!(Money::parse("abc"), None);
+        assert_eq!(Money::from_cents(120050).to_string(), "1200.50");
+        assert_eq!(Money::from_cents(-5).to_string(), "-0.05");
+        assert_eq!(Money::from_cents(120050).to_short_string(), "1200.5");
+        assert_eq!(Money::from_cents(120000).to_short_string(), "1200");
+    }
+}
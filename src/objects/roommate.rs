@@ -0,0 +1,15 @@
+#[derive(Clone, PartialEq, Default)]
+pub struct Roommate {
+    pub name: String,
+    pub pets: i8,
+    pub needs_parking: bool,
+}
+
+impl Roommate {
+    pub fn display_name(&self, index: usize) -> String {
+        if self.name.trim().is_empty() {
+            return format!("Roommate {}", index + 1);
+        }
+        self.name.clone()
+    }
+}
@@ -0,0 +1,131 @@
+use std::cmp::Ordering;
+use crate::objects::entry::Entry;
+use crate::objects::fields::Fields;
+
+const MAX_SORT_KEYS: usize = 3;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Direction {
+    Ascending,
+    Descending,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct SortKey {
+    pub field: Fields,
+    pub direction: Direction,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct SortOrder {
+    keys: Vec<SortKey>,
+}
+
+impl Default for SortOrder {
+    fn default() -> Self {
+        SortOrder { keys: vec![SortKey { field: Fields::Name, direction: Direction::Ascending }] }
+    }
+}
+
+impl Direction {
+    fn flipped(&self) -> Direction {
+        match self {
+            Direction::Ascending => Direction::Descending,
+            Direction::Descending => Direction::Ascending,
+        }
+    }
+}
+
+impl SortOrder {
+    // A plain click sorts by that field alone, flipping direction if it already was the only key.
+    // Adding keeps the existing keys as higher priorities, flipping the field if it is already one of them.
+    pub fn click(&mut self, field: Fields, add: bool) {
+        let only_key = self.keys.len() == 1;
+        if let Some(key) = self.keys.iter_mut().find(|key| key.field == field) {
+            if add || only_key {
+                key.direction = key.direction.flipped();
+                return;
+            }
+        }
+
+        let key = SortKey { field, direction: Direction::Ascending };
+        if add && self.keys.len() < MAX_SORT_KEYS {
+            self.keys.push(key);
+        }
+        else {
+            self.keys = vec![key];
+        }
+    }
+
+    pub fn retain(&mut self, keep: impl Fn(Fields) -> bool) {
+        self.keys.retain(|key| keep(key.field));
+        if self.keys.is_empty() {
+            *self = SortOrder::default();
+        }
+    }
+
+    pub fn compare(&self, a: &Entry, b: &Entry) -> Ordering {
+        self.keys.iter().fold(Ordering::Equal, |ordering, key| {
+            ordering.then_with(|| {
+                let ordering = a.cmp(b, key.field);
+                match key.direction {
+                    Direction::Ascending => ordering,
+                    Direction::Descending => ordering.reverse(),
+                }
+            })
+        })
+    }
+
+    // Arrow for the field's direction, numbered by priority once more than one key is active
+    pub fn indicator(&self, field: Fields) -> Option<String> {
+        let position = self.keys.iter().position(|key| key.field == field)?;
+        let arrow = match self.keys[position].direction {
+            Direction::Ascending => "⏶",
+            Direction::Descending => "⏷",
+        };
+
+        if self.keys.len() > 1 {
+            return Some(format!("{}{}", arrow, position + 1));
+        }
+        Some(arrow.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::with_rent;
+
+    fn sorted(order: &SortOrder) -> Vec<String> {
+        let mut list = [with_rent("b", 90_000), with_rent("a", 90_000), with_rent("c", 80_000)];
+        for (entry, beds) in list.iter_mut().zip([2, 1, 2]) {
+            entry.set_i8(Fields::Beds, beds);
+        }
+        list.sort_by(|a, b| order.compare(a, b));
+        list.iter().map(|entry| entry.get_name()).collect()
+    }
+
+    #[test]
+    fn clicking_the_same_field_flips_direction() {
+        let mut order = SortOrder::default();
+        order.click(Fields::MonthlyRent, false);
+        assert_eq!(sorted(&order), vec!["c", "b", "a"]);
+
+        order.click(Fields::MonthlyRent, false);
+        assert_eq!(order.indicator(Fields::MonthlyRent), Some(String::from("⏷")));
+        assert_eq!(sorted(&order), vec!["b", "a", "c"]);
+    }
+
+    #[test]
+    fn added_keys_break_ties() {
+        let mut order = SortOrder::default();
+        order.click(Fields::Beds, false);
+        order.click(Fields::Beds, false);
+        order.click(Fields::MonthlyRent, true);
+
+        assert_eq!(sorted(&order), vec!["c", "b", "a"]);
+        assert_eq!(order.indicator(Fields::Beds), Some(String::from("⏷1")));
+        assert_eq!(order.indicator(Fields::MonthlyRent), Some(String::from("⏶2")));
+        assert_eq!(order.indicator(Fields::Name), None);
+    }
+}
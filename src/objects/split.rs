@@ -0,0 +1,140 @@
+use crate::objects::money::Money;
+
+#[derive(Clone, PartialEq, Default)]
+pub struct Room {
+    pub name: String,
+    pub rent: Money,
+}
+
+#[derive(Clone, PartialEq, Default)]
+pub enum SplitPolicy {
+    #[default]
+    Equal,
+    SquareFootage(Vec<f32>),
+    Shares(Vec<f32>),
+    Rooms(Vec<Room>),
+}
+
+impl SplitPolicy {
+    pub const ALL_KEYS: [&'static str; 4] = ["equal", "sqft", "shares", "rooms"];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            SplitPolicy::Equal => "equal",
+            SplitPolicy::SquareFootage(_) => "sqft",
+            SplitPolicy::Shares(_) => "shares",
+            SplitPolicy::Rooms(_) => "rooms",
+        }
+    }
+
+    pub fn label(key: &str) -> &'static str {
+        match key {
+            "sqft" => "By bedroom sq. ft.",
+            "shares" => "By percentage",
+            "rooms" => "By named room",
+            _ => "Equal",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Self {
+        match key {
+            "sqft" => SplitPolicy::SquareFootage(Vec::new()),
+            "shares" => SplitPolicy::Shares(Vec::new()),
+            "rooms" => SplitPolicy::Rooms(Vec::new()),
+            _ => SplitPolicy::Equal,
+        }
+    }
+
+    pub fn resized(&self, people: usize) -> Self {
+        match self {
+            SplitPolicy::Equal => SplitPolicy::Equal,
+            SplitPolicy::SquareFootage(weights) => {
+                let mut weights = weights.clone();
+                weights.resize(people, 0.0);
+                SplitPolicy::SquareFootage(weights)
+            },
+            SplitPolicy::Shares(weights) => {
+                let mut weights = weights.clone();
+                weights.resize(people, 0.0);
+                SplitPolicy::Shares(weights)
+            },
+            SplitPolicy::Rooms(rooms) => {
+                let mut rooms = rooms.clone();
+                rooms.resize(people, Room::default());
+                SplitPolicy::Rooms(rooms)
+            },
+        }
+    }
+
+    // Weighted policies fall back to an even split until some weight is filled in.
+    // The shares always add up to exactly the total, see Money::split for the rounding.
+    pub fn allocate(&self, total: Money, people: usize) -> Vec<Money> {
+        match self {
+            SplitPolicy::Equal => total.split_evenly(people),
+            SplitPolicy::SquareFootage(weights) | SplitPolicy::Shares(weights) => {
+                let used: Vec<f64> = (0..people).map(|index| weights.get(index).copied().unwrap_or(0.0) as f64).collect();
+                total.split(&used)
+            },
+            SplitPolicy::Rooms(rooms) => {
+                let room_rents: Vec<Money> = (0..people).map(|index| rooms.get(index).map(|room| room.rent).unwrap_or_default()).collect();
+                let shared = (total - room_rents.iter().sum::<Money>()).split_evenly(people);
+
+                room_rents.iter().zip(shared).map(|(rent, shared)| *rent + shared).collect()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cents(amounts: &[i64]) -> Vec<Money> {
+        amounts.iter().map(|amount| Money::from_cents(*amount)).collect()
+    }
+
+    #[test]
+    fn square_footage_splits_by_bedroom_size() {
+        let policy = SplitPolicy::SquareFootage(vec![300.0, 100.0]);
+
+        assert_eq!(policy.allocate(Money::from_cents(200_000), 2), cents(&[150_000, 50_000]));
+    }
+
+    #[test]
+    fn shares_are_scaled_to_the_total() {
+        assert_eq!(SplitPolicy::Shares(vec![60.0, 40.0]).allocate(Money::from_cents(100_000), 2), cents(&[60_000, 40_000]));
+        // Shares that don't add up to 100 count relative to each other
+        assert_eq!(SplitPolicy::Shares(vec![30.0, 30.0]).allocate(Money::from_cents(100_000), 2), cents(&[50_000, 50_000]));
+    }
+
+    #[test]
+    fn rooms_pay_their_rent_and_share_the_rest() {
+        let rooms = vec![Room { name: String::from("Big"), rent: Money::from_cents(70_000) }, Room { name: String::from("Small"), rent: Money::from_cents(50_000) }];
+        let policy = SplitPolicy::Rooms(rooms);
+
+        assert_eq!(policy.allocate(Money::from_cents(140_000), 2), cents(&[80_000, 60_000]));
+    }
+
+    #[test]
+    fn all_zero_weights_split_evenly() {
+        assert_eq!(SplitPolicy::SquareFootage(vec![0.0, 0.0]).allocate(Money::from_cents(90_000), 2), cents(&[45_000, 45_000]));
+        assert_eq!(SplitPolicy::Shares(Vec::new()).allocate(Money::from_cents(90_000), 3), cents(&[30_000, 30_000, 30_000]));
+    }
+
+    #[test]
+    fn leftover_cents_add_back_up_to_the_total() {
+        let total = Money::from_cents(100_001);
+        let policies = [
+            SplitPolicy::Equal,
+            SplitPolicy::SquareFootage(vec![1.0, 1.0, 1.0]),
+            SplitPolicy::Shares(vec![33.3, 33.3, 33.4]),
+            SplitPolicy::Rooms(vec![Room { name: String::new(), rent: Money::from_cents(1) }; 3]),
+        ];
+
+        for policy in policies {
+            let parts = policy.allocate(total, 3);
+            assert_eq!(parts.len(), 3);
+            assert_eq!(parts.iter().sum::<Money>(), total);
+        }
+    }
+}
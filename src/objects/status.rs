@@ -0,0 +1,75 @@
+// Where we are with a listing, in the order things usually happen
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub enum Status {
+    #[default]
+    Interested,
+    Contacted,
+    Toured,
+    Applied,
+    Approved,
+    Rejected,
+    Signed,
+    Archived,
+}
+
+// When a listing moved to a status, in seconds since the epoch
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Transition {
+    pub status: Status,
+    pub at: u64,
+}
+
+impl Status {
+    pub const ALL: [Status; 8] = [
+        Status::Interested,
+        Status::Contacted,
+        Status::Toured,
+        Status::Applied,
+        Status::Approved,
+        Status::Rejected,
+        Status::Signed,
+        Status::Archived,
+    ];
+
+    // Shown unless the filters say otherwise
+    pub const ACTIVE: [Status; 7] = [
+        Status::Interested,
+        Status::Contacted,
+        Status::Toured,
+        Status::Applied,
+        Status::Approved,
+        Status::Rejected,
+        Status::Signed,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Status::Interested => "interested",
+            Status::Contacted => "contacted",
+            Status::Toured => "toured",
+            Status::Applied => "applied",
+            Status::Approved => "approved",
+            Status::Rejected => "rejected",
+            Status::Signed => "signed",
+            Status::Archived => "archived",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Status::Interested => "Interested",
+            Status::Contacted => "Contacted",
+            Status::Toured => "Toured",
+            Status::Applied => "Applied",
+            Status::Approved => "Approved",
+            Status::Rejected => "Rejected",
+            Status::Signed => "Signed",
+            Status::Archived => "Archived",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        let key = key.trim().to_lowercase();
+        Status::ALL.into_iter().find(|status| status.key() == key)
+    }
+}
@@ -0,0 +1,173 @@
+use std::{env, fs::{create_dir_all, read_to_string, write}, io, path::{Path, PathBuf}};
+
+use jzon::JsonValue;
+
+pub const DATA_ARG: &str = "--data";
+pub const DATA_ENV_VAR: &str = "COST_ANALYSIS_DATA";
+const DATA_PREFIX: &str = "--data=";
+
+const APP_DIR: &str = "cost_analysis";
+const DATA_FILE: &str = "rentdata.json";
+const WORKSPACES_FILE: &str = "workspaces.json";
+const RECENT_KEY: &str = "recent";
+const MAX_RECENT: usize = 8;
+
+fn config_dir() -> Option<PathBuf> {
+    if cfg!(target_os = "windows") {
+        return env::var_os("APPDATA").map(PathBuf::from);
+    }
+
+    if cfg!(target_os = "macos") {
+        return env::var_os("HOME").map(|home| PathBuf::from(home).join("Library").join("Application Support"));
+    }
+
+    match env::var_os("XDG_CONFIG_HOME") {
+        Some(config) if !config.is_empty() => Some(PathBuf::from(config)),
+        _ => env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")),
+    }
+}
+
+fn app_dir() -> PathBuf {
+    match config_dir() {
+        Some(dir) => dir.join(APP_DIR),
+        None => env::current_dir().unwrap_or_default(),
+    }
+}
+
+// The data file comes from `--data <path>`, then the environment, then the platform config directory.
+// Before the config directory it lived in the working directory, so one there is still used until the config one exists
+pub fn resolve_data_path(args: &[String]) -> Result<PathBuf, String> {
+    if let (Some(path), _) = split_data_arg(args)? {
+        return Ok(path);
+    }
+
+    let path = match env::var_os(DATA_ENV_VAR) {
+        Some(path) if !path.is_empty() => PathBuf::from(path),
+        _ => {
+            let configured = app_dir().join(DATA_FILE);
+            let legacy = env::current_dir().unwrap_or_default().join(DATA_FILE);
+            if !configured.exists() && legacy.is_file() { legacy } else { configured }
+        },
+    };
+    Ok(path)
+}
+
+// The first `--data <path>` or `--data=<path>`, and every argument that isn't part of one.
+// A `--data` without a path is an error rather than falling back to the default file
+pub fn split_data_arg(args: &[String]) -> Result<(Option<PathBuf>, Vec<String>), String> {
+    let mut path: Option<PathBuf> = None;
+    let mut rest: Vec<String> = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == DATA_ARG {
+            let Some(value) = iter.next() else { return Err(format!("{} needs a path", DATA_ARG)); };
+            path.get_or_insert_with(|| PathBuf::from(value));
+        }
+        else if let Some(value) = arg.strip_prefix(DATA_PREFIX) {
+            path.get_or_insert_with(|| PathBuf::from(value));
+        }
+        else {
+            rest.push(arg.clone());
+        }
+    }
+    Ok((path, rest))
+}
+
+pub fn load_recent() -> Vec<PathBuf> {
+    let Ok(text) = read_to_string(app_dir().join(WORKSPACES_FILE)) else { return Vec::new(); };
+    let Ok(data) = jzon::parse(&text) else { return Vec::new(); };
+
+    data[RECENT_KEY].members()
+        .filter_map(|path| path.as_str())
+        .map(PathBuf::from)
+        .collect()
+}
+
+// Moves the path to the front of the recent workspaces and saves the list
+pub fn remember_recent(recent: &mut Vec<PathBuf>, path: &Path) -> io::Result<()> {
+    recent.retain(|existing| existing != path);
+    recent.insert(0, path.to_path_buf());
+    recent.truncate(MAX_RECENT);
+
+    let mut paths = JsonValue::new_array();
+    for path in recent.iter() {
+        let _ = paths.push(path.to_string_lossy().as_ref());
+    }
+    let mut data = JsonValue::new_object();
+    data[RECENT_KEY] = paths;
+
+    let dir = app_dir();
+    create_dir_all(&dir)?;
+    write(dir.join(WORKSPACES_FILE), data.dump())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::remove_dir_all;
+    use crate::test_support::workspace;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn the_first_data_path_is_split_off() {
+        let (path, rest) = split_data_arg(&strings(&["list", "--data", "a.json", "--json", "--data=b.json"])).unwrap();
+        assert_eq!(path, Some(PathBuf::from("a.json")));
+        assert_eq!(rest, ["list", "--json"]);
+
+        let (path, rest) = split_data_arg(&strings(&["--data=c.sqlite", "recalc"])).unwrap();
+        assert_eq!(path, Some(PathBuf::from("c.sqlite")));
+        assert_eq!(rest, ["recalc"]);
+
+        assert_eq!(split_data_arg(&strings(&["list"])).unwrap(), (None, strings(&["list"])));
+        assert!(split_data_arg(&strings(&["list", "--data"])).is_err());
+    }
+
+    // Everything that reads the environment or the working directory is in this one test, since both are shared by the test threads
+    #[test]
+    fn data_path_and_recent_workspaces_follow_the_environment() {
+        let dir = workspace("paths");
+        let previous_dir = env::current_dir().unwrap();
+        let _ = remove_dir_all(&dir);
+        create_dir_all(dir.join("cwd")).unwrap();
+        env::set_current_dir(dir.join("cwd")).unwrap();
+        for name in ["XDG_CONFIG_HOME", "APPDATA"] {
+            env::set_var(name, dir.join("config"));
+        }
+        env::set_var("HOME", dir.join("home"));
+        env::remove_var(DATA_ENV_VAR);
+        let configured = app_dir().join(DATA_FILE);
+        assert!(configured.starts_with(&dir));
+
+        // The config directory is the default, unless only the working directory has a file
+        assert_eq!(resolve_data_path(&[]), Ok(configured.clone()));
+        write(dir.join("cwd").join(DATA_FILE), "{}").unwrap();
+        assert_eq!(resolve_data_path(&[]), Ok(env::current_dir().unwrap().join(DATA_FILE)));
+        create_dir_all(app_dir()).unwrap();
+        write(&configured, "{}").unwrap();
+        assert_eq!(resolve_data_path(&[]), Ok(configured));
+
+        env::set_var(DATA_ENV_VAR, dir.join("from_env.json"));
+        assert_eq!(resolve_data_path(&[]), Ok(dir.join("from_env.json")));
+        assert_eq!(resolve_data_path(&strings(&["--data", "from_arg.json"])), Ok(PathBuf::from("from_arg.json")));
+        env::remove_var(DATA_ENV_VAR);
+
+        // Recent workspaces keep the newest first, without repeats
+        assert!(load_recent().is_empty());
+        let mut recent = Vec::new();
+        for index in 0..=MAX_RECENT {
+            remember_recent(&mut recent, &dir.join(format!("{}.json", index))).unwrap();
+        }
+        remember_recent(&mut recent, &dir.join("3.json")).unwrap();
+        let loaded = load_recent();
+        assert_eq!(loaded, recent);
+        assert_eq!(loaded.len(), MAX_RECENT);
+        assert_eq!(loaded[0], dir.join("3.json"));
+        assert_eq!(loaded[1], dir.join(format!("{}.json", MAX_RECENT)));
+
+        env::set_current_dir(previous_dir).unwrap();
+        let _ = remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,29 @@
+use std::fmt::{Display, Formatter, Result};
+use std::path::PathBuf;
+
+pub enum SaveError {
+    Entry { entry: String, error: String },
+    Directory(String),
+    Backup(String),
+    Write { path: PathBuf, error: String },
+    Blocked(PathBuf),
+}
+
+impl SaveError {
+    // A failed backup still leaves the data saved
+    pub fn is_fatal(&self) -> bool {
+        !matches!(self, SaveError::Backup(_))
+    }
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            SaveError::Entry { entry, error } => { write!(f, "Failed to save {}: {}", entry, error) },
+            SaveError::Directory(error) => { write!(f, "Failed to create the data directory: {}", error) },
+            SaveError::Backup(error) => { write!(f, "Failed to back up the data file: {}", error) },
+            SaveError::Write { path, error } => { write!(f, "Failed to save {}: {}", path.display(), error) },
+            SaveError::Blocked(path) => { write!(f, "Not saving over {}, it couldn't be loaded or set aside. Use Save As…, or fix it and open it again", path.display()) },
+        }
+    }
+}
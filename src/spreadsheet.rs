@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+
+use crate::csv;
+use crate::objects::entry::Entry;
+use crate::objects::fields::Fields;
+use crate::objects::format::{parse_count, parse_percent, MoneyFormat};
+use crate::objects::money::Money;
+use crate::objects::roommate::Roommate;
+use crate::objects::status::Status;
+use crate::time;
+
+const YES: &str = "yes";
+const NO: &str = "no";
+pub const PREVIEW_ROWS: usize = 10;
+// A row per listing can't hold these, so exports leave them out and imports leave them as they are
+pub const NOT_IN_CSV: &str = "Recurring fee items, concessions, the rent split and the roommates aren't in CSV files.";
+
+fn cell(entry: &Entry, field: Fields) -> String {
+    match field {
+        Fields::Name => { entry.get_name() },
+        Fields::Link => { entry.get_link() },
+        Fields::Status => { entry.get_status().label().to_string() },
+        Fields::PetDepositPerPet => { String::from(if entry.is_pet_deposit_per_pet() { YES } else { NO }) },
+        _ => {
+            if let Some(amount) = entry.get_money(field) { return amount.to_string(); }
+            if let Some(count) = entry.get_i8(field) { return count.to_string(); }
+            entry.get_f32(field).map(|value| value.to_string()).unwrap_or_default()
+        },
+    }
+}
+
+// Every column the table shows, with plain numbers so spreadsheets can do math on them. See NOT_IN_CSV for the rest
+pub fn export_csv(list: &[Entry], roommates: &[Roommate]) -> String {
+    let mut columns = vec![Fields::Name, Fields::Status];
+    columns.extend(Fields::NUMERIC);
+    columns.extend((0..roommates.len()).map(Fields::Split));
+    columns.push(Fields::Link);
+    columns.push(Fields::PetDepositPerPet);
+
+    let header: Vec<String> = columns.iter().map(|field| match field {
+        Fields::Split(index) => { roommates[*index].display_name(*index) },
+        _ => { field.label() },
+    }).collect();
+
+    let mut text = csv::write_row(&header);
+    for entry in list {
+        let row: Vec<String> = columns.iter().map(|field| cell(entry, *field)).collect();
+        text.push_str(&csv::write_row(&row));
+    }
+    text
+}
+
+pub fn apply_cell(entry: &mut Entry, field: Fields, text: &str, money_format: &MoneyFormat) -> Result<(), String> {
+    let text = text.trim();
+    if text.is_empty() { return Ok(()); }
+
+    match field {
+        Fields::Name => { entry.set_name(text.to_string()); },
+        Fields::Link => { entry.set_link(text.to_string()); },
+        Fields::Status => {
+            let keys: Vec<&str> = Status::ALL.iter().map(|status| status.key()).collect();
+            let status = Status::from_key(text).ok_or(format!("Enter one of {}", keys.join(", ")))?;
+            entry.set_status(status, time::now_seconds());
+        },
+        Fields::PetDepositPerPet => {
+            let per_pet = match text.to_lowercase().as_str() {
+                YES | "true" => true,
+                NO | "false" => false,
+                _ => { return Err(format!("Enter {} or {}", YES, NO)); },
+            };
+            entry.set_pet_deposit_per_pet(per_pet);
+        },
+        _ if entry.get_money(field).is_some() => {
+            // Exports from here use plain numbers whatever the locale
+            let amount = money_format.parse(text).or_else(|problem| Money::parse(text).ok_or(problem))?;
+            entry.set_money(field, amount);
+        },
+        _ if entry.get_i8(field).is_some() => { entry.set_i8(field, parse_count(text)?); },
+        _ if entry.get_f32(field).is_some() => { entry.set_f32(field, parse_percent(text)?); },
+        _ => { return Err(String::from("can't be imported")); },
+    }
+    Ok(())
+}
+
+fn normalize(text: &str) -> String {
+    text.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase()
+}
+
+// Matches a column header or argument against field labels and keys, ignoring case and punctuation
+pub fn input_field(name: &str) -> Option<Fields> {
+    let name = normalize(name);
+    Fields::INPUTS.into_iter().find(|field| normalize(&field.label()) == name || field.key() == name)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Duplicates {
+    Skip,
+    Update,
+    Add,
+}
+
+impl Duplicates {
+    pub const ALL: [Duplicates; 3] = [Duplicates::Skip, Duplicates::Update, Duplicates::Add];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Duplicates::Skip => "Skip them",
+            Duplicates::Update => "Update the existing listing",
+            Duplicates::Add => "Add them anyway",
+        }
+    }
+}
+
+pub enum RowPlan {
+    New(Entry),
+    Update(Entry),
+    Skip(String),
+    Invalid(String),
+}
+
+pub struct ImportWizard {
+    pub source: String,
+    pub rows: Vec<Vec<String>>,
+    pub has_header: bool,
+    pub mapping: Vec<Option<Fields>>,
+    pub duplicates: Duplicates,
+}
+
+impl ImportWizard {
+    pub fn new(source: String, rows: Vec<Vec<String>>) -> Self {
+        let columns = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut wizard = ImportWizard { source, rows, has_header: true, mapping: vec![None; columns], duplicates: Duplicates::Skip };
+        wizard.auto_map();
+        wizard
+    }
+
+    pub fn auto_map(&mut self) {
+        let header = self.rows.first().cloned().unwrap_or_default();
+        for (column, mapping) in self.mapping.iter_mut().enumerate() {
+            *mapping = header.get(column).and_then(|name| input_field(name));
+        }
+        if self.mapping.iter().all(|mapping| mapping.is_none()) {
+            self.has_header = false;
+        }
+    }
+
+    pub fn column_name(&self, column: usize) -> String {
+        match self.rows.first().and_then(|row| row.get(column)).filter(|_| self.has_header) {
+            Some(name) if !name.trim().is_empty() => { name.clone() },
+            _ => { format!("Column {}", column + 1) },
+        }
+    }
+
+    pub fn data_rows(&self) -> &[Vec<String>] {
+        if self.has_header && !self.rows.is_empty() { &self.rows[1..] } else { &self.rows }
+    }
+
+    pub fn plan(&self, list: &[Entry], money_format: &MoneyFormat) -> Vec<RowPlan> {
+        let Some(name_column) = self.mapping.iter().position(|mapping| *mapping == Some(Fields::Name)) else {
+            return self.data_rows().iter().map(|_| RowPlan::Invalid(String::from("No column is mapped to Name"))).collect();
+        };
+
+        // Rows in the file count as the first row's duplicates the same way already listed names do
+        let first_row = if self.has_header && !self.rows.is_empty() { 2 } else { 1 };
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        self.data_rows().iter().enumerate().map(|(index, row)| {
+            let name = row.get(name_column).map(|name| name.trim()).unwrap_or_default();
+            if name.is_empty() { return RowPlan::Invalid(String::from("The name is empty")); }
+
+            let earlier = *seen.entry(name.to_lowercase()).or_insert(first_row + index);
+            if earlier != first_row + index && self.duplicates != Duplicates::Add {
+                return RowPlan::Skip(format!("Same name as row {}", earlier));
+            }
+
+            let existing = list.iter().find(|entry| entry.get_name().trim().eq_ignore_ascii_case(name));
+            let mut entry = match (existing, self.duplicates) {
+                (Some(existing), Duplicates::Skip) => { return RowPlan::Skip(format!("Already listed as {}", existing.get_name())); },
+                (Some(existing), Duplicates::Update) => { existing.clone() },
+                _ => { Entry::default() },
+            };
+
+            for (column, field) in self.mapping.iter().enumerate() {
+                let Some(field) = field else { continue; };
+                let text = row.get(column).map(String::as_str).unwrap_or_default();
+                if let Err(problem) = apply_cell(&mut entry, *field, text, money_format) {
+                    return RowPlan::Invalid(format!("{}: {}", field.label(), problem));
+                }
+            }
+
+            match (existing, self.duplicates) {
+                (Some(_), Duplicates::Update) => RowPlan::Update(entry),
+                _ => RowPlan::New(entry),
+            }
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::named;
+
+    fn wizard(text: &str) -> ImportWizard {
+        ImportWizard::new(String::from("test.csv"), csv::parse(text).unwrap())
+    }
+
+    #[test]
+    fn exports_inputs_computed_columns_and_splits() {
+        let mut entry = named(1, "Oak, Unit 2");
+        entry.set_money(Fields::MonthlyRent, Money::from_cents(120000));
+        entry.calculate(&[Roommate::default(), Roommate::default()]);
+
+        let rows = csv::parse(&export_csv(&[entry], &[Roommate::default(), Roommate::default()])).unwrap();
+        let column = |label: &str| rows[0].iter().position(|header| header == label).unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1][column("Name")], "Oak, Unit 2");
+        assert_eq!(rows[1][column("Status")], "Interested");
+        assert_eq!(rows[1][column("Monthly Rent")], "1200.00");
+        assert_eq!(rows[1][column("Roommate 2")], "600.00");
+        assert_eq!(rows[1][column(&Fields::PetDepositPerPet.label())], NO);
+    }
+
+    #[test]
+    fn exports_import_back() {
+        let mut entry = named(1, "Oak");
+        entry.set_money(Fields::PetDeposit, Money::from_cents(30000));
+        entry.set_pet_deposit_per_pet(true);
+
+        let import = wizard(&export_csv(&[entry], &[]));
+        assert!(import.mapping.contains(&Some(Fields::PetDepositPerPet)));
+        let plan = import.plan(&[], &MoneyFormat::default());
+        let RowPlan::New(imported) = &plan[0] else { panic!("expected a new listing") };
+        assert!(imported.is_pet_deposit_per_pet());
+        assert_eq!(imported.get_money(Fields::PetDeposit), Some(Money::from_cents(30000)));
+    }
+
+    #[test]
+    fn maps_headers_and_flags_duplicates() {
+        let mut import = wizard("name,Monthly Rent,Number of Beds,Notes\nOak,\"$1,200.50\",2,corner\nPine,950,x,\n");
+        let list = vec![named(7, "oak")];
+        let format = MoneyFormat::default();
+
+        assert_eq!(import.mapping, vec![Some(Fields::Name), Some(Fields::MonthlyRent), Some(Fields::Beds), None]);
+        assert!(matches!(import.plan(&list, &format)[0], RowPlan::Skip(_)));
+
+        import.duplicates = Duplicates::Update;
+        let plan = import.plan(&list, &format);
+        let RowPlan::Update(updated) = &plan[0] else { panic!("expected an update") };
+        assert_eq!(updated.get_id(), 7);
+        assert_eq!(updated.get_money(Fields::MonthlyRent), Some(Money::from_cents(120050)));
+        assert_eq!(updated.get_i8(Fields::Beds), Some(2));
+        assert!(matches!(&plan[1], RowPlan::Invalid(problem) if problem.starts_with("Number of Beds")));
+    }
+
+    #[test]
+    fn repeated_names_in_the_file_are_flagged() {
+        let mut import = wizard("Name,Monthly Rent\nOak,900\nElm,800\n oak ,950\n");
+        let plan = import.plan(&[], &MoneyFormat::default());
+
+        assert!(matches!(&plan[0], RowPlan::New(_)));
+        assert!(matches!(&plan[1], RowPlan::New(_)));
+        assert!(matches!(&plan[2], RowPlan::Skip(reason) if reason == "Same name as row 2"));
+
+        import.duplicates = Duplicates::Add;
+        assert!(matches!(&import.plan(&[], &MoneyFormat::default())[2], RowPlan::New(_)));
+    }
+
+    #[test]
+    fn headerless_files_need_a_name_column() {
+        let mut import = wizard("Oak,900\n");
+
+        assert!(!import.has_header);
+        assert!(matches!(import.plan(&[], &MoneyFormat::default())[0], RowPlan::Invalid(_)));
+
+        import.mapping[0] = Some(Fields::Name);
+        import.mapping[1] = Some(Fields::MonthlyRent);
+        assert!(matches!(&import.plan(&[], &MoneyFormat::default())[0], RowPlan::New(entry) if entry.get_name() == "Oak"));
+    }
+}
@@ -0,0 +1,91 @@
+use std::{fs::read_to_string, path::{Path, PathBuf}};
+
+use jzon::JsonValue;
+
+use crate::backups;
+use crate::load_error::LoadError;
+use crate::objects::entry::Entry;
+use crate::save_error::SaveError;
+use crate::storage::{create_parent, set_aside, Changes, Loaded, Settings, Storage};
+use crate::store;
+
+// The whole workspace in one JSON file, rewritten on every save
+pub struct JsonStorage {
+    path: PathBuf,
+    read_only: bool,
+    blocked: bool,
+}
+
+impl JsonStorage {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, read_only: false, blocked: false }
+    }
+
+    // Loads without moving or copying a file that has problems, for callers that never save
+    pub fn read_only(path: PathBuf) -> Self {
+        Self { path, read_only: true, blocked: false }
+    }
+
+    // A file that couldn't be loaded is still there unless it was moved aside, and saving would overwrite it
+    fn fail_load(&mut self, error: LoadError) -> Loaded {
+        let mut loaded = Loaded::default();
+        loaded.report.problems.push(error);
+        if !self.read_only {
+            set_aside(&self.path, &mut loaded.report, false);
+        }
+        self.blocked = loaded.report.quarantined.is_none();
+        loaded
+    }
+}
+
+impl Storage for JsonStorage {
+    fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load(&mut self) -> Loaded {
+        self.blocked = false;
+        let text = if self.path.exists() {
+            match read_to_string(&self.path) {
+                Ok(text) => text,
+                Err(error) => { return self.fail_load(LoadError::Unreadable(error.to_string())); },
+            }
+        }
+        else {
+            String::new()
+        };
+
+        let data = if text.trim().is_empty() {
+            JsonValue::new_object()
+        }
+        else {
+            match jzon::parse(&text) {
+                Ok(data) if data.is_object() => data,
+                Ok(_) => { return self.fail_load(LoadError::NotAnObject); },
+                Err(error) => { return self.fail_load(LoadError::Malformed(error.to_string())); },
+            }
+        };
+
+        let mut loaded = store::read_document(data);
+        if !loaded.report.problems.is_empty() && !self.read_only {
+            set_aside(&self.path, &mut loaded.report, true);
+        }
+        loaded
+    }
+
+    fn save(&mut self, settings: &Settings, entries: &[Entry], _changes: &Changes) -> Vec<SaveError> {
+        if self.blocked { return vec![SaveError::Blocked(self.path.clone())]; }
+        let mut problems: Vec<SaveError> = Vec::new();
+        let data = store::save_document(settings, entries, &mut problems).dump();
+
+        create_parent(&self.path, &mut problems);
+        if let Err(error) = backups::create_backup(&self.path, settings.backup_count) {
+            problems.push(SaveError::Backup(error.to_string()));
+        }
+        if let Err(error) = backups::atomic_write(&self.path, data.as_bytes()) {
+            problems.push(SaveError::Write { path: self.path.clone(), error: error.to_string() });
+        }
+
+        problems
+    }
+}
@@ -0,0 +1,114 @@
+use std::{collections::HashSet, fs::create_dir_all, path::{Path, PathBuf}};
+
+use crate::backups::{self, Backup};
+use crate::load_error::LoadError;
+use crate::objects::entry::Entry;
+use crate::objects::filter::Filters;
+use crate::objects::format::MoneyFormat;
+use crate::objects::roommate::Roommate;
+use crate::save_error::SaveError;
+use crate::store::LoadReport;
+
+pub mod json;
+pub mod sqlite;
+
+const SQLITE_EXTENSIONS: [&str; 3] = ["sqlite", "sqlite3", "db"];
+
+#[derive(Clone)]
+pub struct Settings {
+    pub roommates: Vec<Roommate>,
+    pub money_format: MoneyFormat,
+    pub filters: Filters,
+    pub backup_count: i8,
+    pub next_id: u64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            roommates: vec![Roommate::default(); 2],
+            money_format: MoneyFormat::default(),
+            filters: Filters::default(),
+            backup_count: backups::DEFAULT_BACKUP_COUNT,
+            next_id: 1,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Loaded {
+    pub settings: Settings,
+    pub entries: Vec<Entry>,
+    pub report: LoadReport,
+}
+
+// The entries added, edited or removed since the last save, so a store can write only those
+#[derive(Default)]
+pub struct Changes {
+    pub entries: HashSet<u64>,
+    pub everything: bool,
+}
+
+pub trait Storage {
+    fn get_path(&self) -> &Path;
+
+    // Never fails outright, whatever couldn't be loaded is in the report.
+    // A missing file is a fresh workspace, the first save creates it
+    fn load(&mut self) -> Loaded;
+
+    fn save(&mut self, settings: &Settings, entries: &[Entry], changes: &Changes) -> Vec<SaveError>;
+
+    fn list_backups(&self) -> Vec<Backup> {
+        backups::list_backups(self.get_path())
+    }
+
+    fn restore_backup(&mut self, backup: &Path, keep: i8) -> Result<(), String> {
+        restore(self.get_path(), backup, keep)
+    }
+}
+
+pub fn is_sqlite(path: &Path) -> bool {
+    path.extension().and_then(|extension| extension.to_str())
+        .is_some_and(|extension| SQLITE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
+
+pub fn open(path: PathBuf) -> Box<dyn Storage> {
+    if is_sqlite(&path) {
+        Box::new(sqlite::SqliteStorage::new(path))
+    }
+    else {
+        Box::new(json::JsonStorage::new(path))
+    }
+}
+
+pub fn open_read_only(path: PathBuf) -> Box<dyn Storage> {
+    if is_sqlite(&path) {
+        Box::new(sqlite::SqliteStorage::read_only(path))
+    }
+    else {
+        Box::new(json::JsonStorage::read_only(path))
+    }
+}
+
+fn restore(path: &Path, backup: &Path, keep: i8) -> Result<(), String> {
+    // Keep what is being replaced, so a restore can itself be undone from the list
+    backups::snapshot(path, keep).map_err(|error| format!("Failed to back up before restoring: {}", error))?;
+    backups::restore_backup(path, backup).map_err(|error| format!("Failed to restore backup: {}", error))
+}
+
+// Keeps a copy of a file that loaded with problems, since the next save won't include what was dropped.
+// A file that couldn't be loaded at all is moved aside instead, so the next save can't overwrite it
+fn set_aside(path: &Path, report: &mut LoadReport, keep_original: bool) {
+    match backups::quarantine(path, keep_original) {
+        Ok(quarantined) => { report.quarantined = Some(quarantined); },
+        Err(error) => { report.problems.push(LoadError::QuarantineFailed(error.to_string())); },
+    }
+}
+
+fn create_parent(path: &Path, problems: &mut Vec<SaveError>) {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        if let Err(error) = create_dir_all(parent) {
+            problems.push(SaveError::Directory(error.to_string()));
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use std::path::{Path, PathBuf};
+
+use jzon::JsonValue;
+use rusqlite::{params, Connection, OpenFlags};
+
+use crate::backups;
+use crate::load_error::LoadError;
+use crate::objects::entry::Entry;
+use crate::save_error::SaveError;
+use crate::storage::{create_parent, restore, set_aside, Changes, Loaded, Settings, Storage};
+use crate::store;
+
+// Property values and listings are kept as the same JSON as in a data file, so they share the migrations
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS properties (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+    CREATE TABLE IF NOT EXISTS listings (id INTEGER PRIMARY KEY, name TEXT NOT NULL, data TEXT NOT NULL);
+";
+
+// One row per listing, so a save only writes the listings that changed
+pub struct SqliteStorage {
+    path: PathBuf,
+    connection: Option<Connection>,
+    read_only: bool,
+    blocked: bool,
+    // Rows that couldn't be built, the next save deletes them once they have been copied aside
+    dropped: Vec<u64>,
+}
+
+impl SqliteStorage {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, connection: None, read_only: false, blocked: false, dropped: Vec::new() }
+    }
+
+    // Loads without moving or copying a database that has problems, for callers that never save
+    pub fn read_only(path: PathBuf) -> Self {
+        Self { path, connection: None, read_only: true, blocked: false, dropped: Vec::new() }
+    }
+
+    fn connect(&mut self) -> rusqlite::Result<&mut Connection> {
+        if self.connection.is_none() {
+            // A read-only open never creates the tables, a database without them is unreadable
+            let connection = if self.read_only {
+                Connection::open_with_flags(&self.path, OpenFlags::SQLITE_OPEN_READ_ONLY)?
+            }
+            else {
+                let connection = Connection::open(&self.path)?;
+                connection.execute_batch(SCHEMA)?;
+                connection
+            };
+            self.connection = Some(connection);
+        }
+        Ok(self.connection.as_mut().unwrap())
+    }
+
+    fn read(&mut self) -> rusqlite::Result<JsonValue> {
+        let connection = self.connect()?;
+
+        let mut properties = JsonValue::new_object();
+        let mut statement = connection.prepare("SELECT key, value FROM properties")?;
+        let rows = statement.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        for row in rows {
+            let (key, value) = row?;
+            properties[key.as_str()] = jzon::parse(&value).unwrap_or(JsonValue::Null);
+        }
+
+        // A listing that isn't valid JSON is reported by build like any other bad listing
+        let mut listings = JsonValue::new_object();
+        let mut statement = connection.prepare("SELECT id, data FROM listings")?;
+        let rows = statement.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+        for row in rows {
+            let (id, data) = row?;
+            listings[id.to_string().as_str()] = jzon::parse(&data).unwrap_or(JsonValue::Null);
+        }
+
+        if properties.is_empty() && listings.is_empty() {
+            return Ok(JsonValue::new_object());
+        }
+        Ok(store::document(properties, listings))
+    }
+
+    fn write(&mut self, settings: &Settings, entries: &[Entry], changes: &Changes, problems: &mut Vec<SaveError>) -> rusqlite::Result<()> {
+        let dropped = self.dropped.clone();
+        let transaction = self.connect()?.transaction()?;
+
+        transaction.execute("DELETE FROM properties", [])?;
+        for (key, value) in store::save_properties(settings).entries() {
+            transaction.execute("INSERT INTO properties (key, value) VALUES (?1, ?2)", params![key, value.dump()])?;
+        }
+
+        let ids: Vec<u64> = if changes.everything {
+            transaction.execute("DELETE FROM listings", [])?;
+            entries.iter().map(|entry| entry.get_id()).collect()
+        }
+        else {
+            changes.entries.iter().copied().collect()
+        };
+        for id in dropped {
+            if !entries.iter().any(|entry| entry.is(id)) {
+                transaction.execute("DELETE FROM listings WHERE id = ?1", params![id as i64])?;
+            }
+        }
+        for id in ids {
+            let Some(entry) = entries.iter().find(|entry| entry.is(id)) else {
+                transaction.execute("DELETE FROM listings WHERE id = ?1", params![id as i64])?;
+                continue;
+            };
+            match store::save(entry) {
+                Ok(data) => {
+                    transaction.execute("INSERT OR REPLACE INTO listings (id, name, data) VALUES (?1, ?2, ?3)", params![id as i64, entry.get_name(), data.dump()])?;
+                },
+                Err(error) => { problems.push(SaveError::Entry { entry: entry.get_name(), error: error.to_string() }); },
+            }
+        }
+
+        transaction.commit()?;
+        self.dropped.clear();
+        Ok(())
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load(&mut self) -> Loaded {
+        self.blocked = false;
+        self.dropped.clear();
+        if !self.path.exists() { return Loaded::default(); }
+
+        match self.read() {
+            Ok(data) => {
+                let ids: Vec<u64> = data[store::RENT_DATA_KEY].entries().filter_map(|(key, _)| key.parse().ok()).collect();
+                let mut loaded = store::read_document(data);
+                if !loaded.report.problems.is_empty() && !self.read_only {
+                    set_aside(&self.path, &mut loaded.report, true);
+                }
+                // Without a copy the bad rows stay, so nothing that couldn't be loaded is lost
+                if loaded.report.quarantined.is_some() {
+                    self.dropped = ids.into_iter().filter(|id| !loaded.entries.iter().any(|entry| entry.is(*id))).collect();
+                }
+                loaded
+            },
+            Err(error) => {
+                self.connection = None;
+                let mut loaded = Loaded::default();
+                loaded.report.problems.push(LoadError::Unreadable(error.to_string()));
+                if !self.read_only {
+                    set_aside(&self.path, &mut loaded.report, false);
+                }
+                // Same as for a data file, saving would write into what couldn't be read
+                self.blocked = loaded.report.quarantined.is_none();
+                loaded
+            },
+        }
+    }
+
+    fn save(&mut self, settings: &Settings, entries: &[Entry], changes: &Changes) -> Vec<SaveError> {
+        if self.blocked { return vec![SaveError::Blocked(self.path.clone())]; }
+        let mut problems: Vec<SaveError> = Vec::new();
+
+        create_parent(&self.path, &mut problems);
+        if let Err(error) = backups::create_backup(&self.path, settings.backup_count) {
+            problems.push(SaveError::Backup(error.to_string()));
+        }
+        if let Err(error) = self.write(settings, entries, changes, &mut problems) {
+            problems.push(SaveError::Write { path: self.path.clone(), error: error.to_string() });
+        }
+
+        problems
+    }
+
+    // The file is swapped out underneath the connection, so it has to be reopened
+    fn restore_backup(&mut self, backup: &Path, keep: i8) -> Result<(), String> {
+        self.connection = None;
+        restore(&self.path, backup, keep)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, remove_dir_all, write};
+    use crate::storage::open;
+    use crate::store::Portfolio;
+    use crate::test_support::workspace;
+
+    #[test]
+    fn only_changed_listings_are_written() {
+        let dir = workspace("sqlite_changes");
+        let path = dir.join("rentdata.sqlite");
+        let (mut portfolio, _) = Portfolio::open(path.clone());
+        let oak = portfolio.new_entry();
+        let elm = portfolio.new_entry();
+        assert!(portfolio.save().is_empty());
+
+        // A row changed behind the portfolio's back survives saves that don't touch it
+        let connection = Connection::open(&path).unwrap();
+        connection.execute("UPDATE listings SET name = 'Outside' WHERE id = ?1", params![elm as i64]).unwrap();
+        portfolio.update(oak, "rename", |entry| entry.set_name(String::from("Oak")));
+        portfolio.remove(elm);
+        portfolio.new_entry();
+        assert!(portfolio.save().is_empty());
+
+        let names: Vec<String> = connection.prepare("SELECT name FROM listings ORDER BY id").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .map(|name| name.unwrap())
+            .collect();
+        assert_eq!(names, ["Oak", "New_3"]);
+
+        let (reopened, report) = Portfolio::open(path);
+        assert!(report.problems.is_empty());
+        assert_eq!(reopened.entries().len(), 2);
+        assert_eq!(reopened.get(oak).unwrap().get_name(), "Oak");
+
+        let _ = remove_dir_all(dir);
+    }
+
+    #[test]
+    fn files_that_are_not_databases_are_set_aside() {
+        let dir = workspace("sqlite_corrupt");
+        let path = dir.join("rentdata.db");
+        create_dir_all(&dir).unwrap();
+        write(&path, "definitely not sqlite, just some text that is long enough to have a header").unwrap();
+
+        let loaded = open(path.clone()).load();
+        assert!(loaded.report.is_fatal());
+        assert!(!path.exists());
+
+        let _ = remove_dir_all(dir);
+    }
+
+    #[test]
+    fn rows_that_fail_to_build_are_dropped_on_the_next_save() {
+        let dir = workspace("sqlite_dropped");
+        let path = dir.join("rentdata.sqlite");
+        let (mut portfolio, _) = Portfolio::open(path.clone());
+        portfolio.new_entry();
+        assert!(portfolio.save().is_empty());
+        let connection = Connection::open(&path).unwrap();
+        connection.execute("INSERT INTO listings (id, name, data) VALUES (7, 'Broken', 'not json')", []).unwrap();
+
+        let (mut portfolio, report) = Portfolio::open(path.clone());
+        assert_eq!(report.problems.len(), 1);
+        assert!(report.quarantined.is_some());
+        portfolio.new_entry();
+        assert!(portfolio.save().is_empty());
+
+        let count: i64 = connection.query_row("SELECT COUNT(*) FROM listings WHERE id = 7", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 0);
+        let (_, report) = Portfolio::open(path);
+        assert!(report.problems.is_empty());
+        assert!(report.quarantined.is_none());
+
+        let _ = remove_dir_all(dir);
+    }
+
+    #[test]
+    fn read_only_opens_leave_the_database_untouched() {
+        let dir = workspace("sqlite_read_only");
+        let path = dir.join("rentdata.sqlite");
+        create_dir_all(&dir).unwrap();
+        Connection::open(&path).unwrap().execute_batch("CREATE TABLE other (id INTEGER)").unwrap();
+
+        let loaded = SqliteStorage::read_only(path.clone()).load();
+        assert!(loaded.report.is_fatal());
+        let tables: i64 = Connection::open(&path).unwrap()
+            .query_row("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table'", [], |row| row.get(0)).unwrap();
+        assert_eq!(tables, 1);
+
+        let _ = remove_dir_all(dir);
+    }
+}
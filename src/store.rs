@@ -0,0 +1,876 @@
+use std::path::{Path, PathBuf};
+
+use jzon::JsonValue;
+
+use crate::backups::Backup;
+use crate::history::{Command, History};
+use crate::load_error::LoadError;
+use crate::migrations;
+use crate::objects::concession::{Concession, ConcessionKind};
+use crate::objects::entry::Entry;
+use crate::objects::fields::Fields;
+use crate::objects::filter::{Filters, RangeFilter};
+use crate::objects::format::{Currency, Locale, MoneyFormat};
+use crate::objects::line_item::LineItem;
+use crate::objects::money::Money;
+use crate::objects::roommate::Roommate;
+use crate::objects::sort::SortOrder;
+use crate::objects::split::{Room, SplitPolicy};
+use crate::objects::status::{Status, Transition};
+use crate::save_error::SaveError;
+use crate::spreadsheet::RowPlan;
+use crate::storage::{self, Changes, Loaded, Settings, Storage};
+use crate::time;
+
+const NAME_KEY: &str = "name";
+const BED_KEY: &str = "beds";
+const BATH_KEY: &str = "baths";
+const DEPOSIT_KEY: &str = "deposit";
+const PET_DEPOSIT_KEY: &str = "petdeposit";
+const PET_DEPOSIT_PER_PET_KEY: &str = "petdepositperpet";
+const PET_FEE_KEY: &str = "petfee";
+const MOVE_IN_FEES_KEY: &str = "moveinfees";
+const PET_MONTHLY_KEY: &str = "petmonthly";
+const PARKING_SPOTS_KEY: &str = "parkingspots";
+const PARKING_MONTHLY_KEY: &str = "parkingmonthly";
+const MONTHLY_RENT_KEY: &str = "monthlyrent";
+const LEASE_MONTHS_KEY: &str = "leasemonths";
+const DEPOSIT_REFUND_KEY: &str = "depositrefund";
+const LINK_KEY: &str = "link";
+const LINE_ITEMS_KEY: &str = "lineitems";
+const LINE_ITEM_NAME_KEY: &str = "name";
+const LINE_ITEM_AMOUNT_KEY: &str = "amount";
+const CONCESSIONS_KEY: &str = "concessions";
+const CONCESSION_KIND_KEY: &str = "kind";
+const CONCESSION_AMOUNT_KEY: &str = "amount";
+const CONCESSION_COUNT_KEY: &str = "count";
+const STATUS_KEY: &str = "status";
+const STATUS_HISTORY_KEY: &str = "statushistory";
+const TRANSITION_STATUS_KEY: &str = "status";
+const TRANSITION_AT_KEY: &str = "at";
+const SPLIT_KEY: &str = "split";
+const SPLIT_POLICY_KEY: &str = "policy";
+const SPLIT_VALUES_KEY: &str = "values";
+const SPLIT_ROOMS_KEY: &str = "rooms";
+const ROOM_NAME_KEY: &str = "name";
+const ROOM_RENT_KEY: &str = "rent";
+pub(crate) const RENT_DATA_KEY: &str = "rentdata";
+const PROPERTIES_KEY: &str = "properties";
+
+const ROOMMATES_KEY: &str = "roommates";
+const CURRENCY_KEY: &str = "currency";
+const LOCALE_KEY: &str = "locale";
+const FILTERS_KEY: &str = "filters";
+const BACKUP_COUNT_KEY: &str = "backups";
+const NEXT_ID_KEY: &str = "nextid";
+const FILTER_NAME_KEY: &str = "name";
+const FILTER_LINK_KEY: &str = "link";
+const FILTER_RANGES_KEY: &str = "ranges";
+const FILTER_FIELD_KEY: &str = "field";
+const FILTER_MIN_KEY: &str = "min";
+const FILTER_MAX_KEY: &str = "max";
+const FILTER_STATUSES_KEY: &str = "statuses";
+const ROOMMATE_NAME_KEY: &str = "name";
+const ROOMMATE_PETS_KEY: &str = "pets";
+const ROOMMATE_PARKING_KEY: &str = "parking";
+
+fn fetch_i8(data: &JsonValue, key: &str) -> Option<i8> {
+    data.get(key)?.as_i8()
+}
+
+fn fetch_f32(data: &JsonValue, key: &str) -> Option<f32> {
+    data.get(key)?.as_f32()
+}
+
+fn fetch_money(data: &JsonValue, key: &str) -> Option<Money> {
+    data.get(key)?.as_f64().map(Money::from_f64)
+}
+
+fn require<T>(name: &str, data: &JsonValue, key: &'static str, value: Option<T>) -> Result<T, LoadError> {
+    match value {
+        Some(value) => Ok(value),
+        None if data.has_key(key) => Err(LoadError::InvalidValue { entry: name.to_string(), key }),
+        None => Err(LoadError::MissingKey { entry: name.to_string(), key }),
+    }
+}
+
+pub fn build(id: u64, data: &JsonValue) -> Result<Entry, LoadError> {
+    let listing = format!("#{}", id);
+    if !data.is_object() { return Err(LoadError::InvalidEntry { entry: listing }); }
+    let name = require(&listing, data, NAME_KEY, data[NAME_KEY].as_str())?;
+
+    let beds = require(name, data, BED_KEY, fetch_i8(data, BED_KEY))?;
+    let baths = require(name, data, BATH_KEY, fetch_i8(data, BATH_KEY))?;
+    let deposit = require(name, data, DEPOSIT_KEY, fetch_money(data, DEPOSIT_KEY))?;
+    let pet_deposit = require(name, data, PET_DEPOSIT_KEY, fetch_money(data, PET_DEPOSIT_KEY))?;
+    let pet_monthly = require(name, data, PET_MONTHLY_KEY, fetch_money(data, PET_MONTHLY_KEY))?;
+    let parking_monthly = require(name, data, PARKING_MONTHLY_KEY, fetch_money(data, PARKING_MONTHLY_KEY))?;
+    let monthly_rent = require(name, data, MONTHLY_RENT_KEY, fetch_money(data, MONTHLY_RENT_KEY))?;
+
+    let mut entry = Entry::new(
+        String::from(name),
+        beds, 
+        baths, 
+        deposit, 
+        pet_deposit, 
+        pet_monthly, 
+        parking_monthly, 
+        monthly_rent, 
+        data[LINK_KEY].as_str().unwrap_or_default().to_string());
+    entry.set_id(id);
+    entry.set_i8(Fields::ParkingSpots, fetch_i8(data, PARKING_SPOTS_KEY).unwrap_or(1));
+    entry.set_pet_deposit_per_pet(data[PET_DEPOSIT_PER_PET_KEY].as_bool().unwrap_or(false));
+    entry.set_money(Fields::PetFee, fetch_money(data, PET_FEE_KEY).unwrap_or_default());
+    entry.set_money(Fields::MoveInFees, fetch_money(data, MOVE_IN_FEES_KEY).unwrap_or_default());
+    entry.set_i8(Fields::LeaseMonths, fetch_i8(data, LEASE_MONTHS_KEY).unwrap_or(12));
+    entry.set_f32(Fields::DepositRefund, fetch_f32(data, DEPOSIT_REFUND_KEY).unwrap_or(100.0));
+    entry.set_split_policy(build_split(&data[SPLIT_KEY]));
+    entry.set_concessions(build_concessions(&data[CONCESSIONS_KEY]));
+    entry.set_line_items(build_line_items(&data[LINE_ITEMS_KEY]));
+    let status = data[STATUS_KEY].as_str().and_then(Status::from_key).unwrap_or_default();
+    entry.restore_status(status, build_status_history(&data[STATUS_HISTORY_KEY]));
+
+    Ok(entry)
+}
+
+fn build_split(data: &JsonValue) -> SplitPolicy {
+    let values: Vec<f32> = data[SPLIT_VALUES_KEY].members().filter_map(|value| value.as_f32()).collect();
+
+    match SplitPolicy::from_key(data[SPLIT_POLICY_KEY].as_str().unwrap_or_default()) {
+        SplitPolicy::SquareFootage(_) => SplitPolicy::SquareFootage(values),
+        SplitPolicy::Shares(_) => SplitPolicy::Shares(values),
+        SplitPolicy::Rooms(_) => {
+            let rooms = data[SPLIT_ROOMS_KEY].members().map(|room| Room {
+                name: room[ROOM_NAME_KEY].as_str().unwrap_or_default().to_string(),
+                rent: fetch_money(room, ROOM_RENT_KEY).unwrap_or_default(),
+            }).collect();
+            SplitPolicy::Rooms(rooms)
+        },
+        SplitPolicy::Equal => SplitPolicy::Equal,
+    }
+}
+
+fn build_line_items(data: &JsonValue) -> Vec<LineItem> {
+    data.members().filter_map(|item| {
+        Some(LineItem {
+            name: item[LINE_ITEM_NAME_KEY].as_str()?.to_string(),
+            amount: fetch_money(item, LINE_ITEM_AMOUNT_KEY)?,
+        })
+    }).collect()
+}
+
+fn save_line_items(line_items: &[LineItem]) -> JsonValue {
+    let mut line_items_value = JsonValue::new_array();
+    for item in line_items {
+        let mut item_value = JsonValue::new_object();
+        item_value[LINE_ITEM_NAME_KEY] = JsonValue::from(item.name.as_str());
+        item_value[LINE_ITEM_AMOUNT_KEY] = JsonValue::from(item.amount.as_f64());
+        let _ = line_items_value.push(item_value);
+    }
+    line_items_value
+}
+
+// Free time used to be kept in the amount, so that is the count when there isn't one
+fn build_concessions(data: &JsonValue) -> Vec<Concession> {
+    data.members().filter_map(|concession| {
+        let kind = ConcessionKind::from_key(concession[CONCESSION_KIND_KEY].as_str()?)?;
+        if kind.is_free_time() {
+            let count = concession[CONCESSION_COUNT_KEY].as_i8()
+                .or_else(|| concession[CONCESSION_AMOUNT_KEY].as_f64().map(|amount| amount.round().clamp(0.0, i8::MAX as f64) as i8))?;
+            Some(Concession { kind, count, ..Concession::default() })
+        }
+        else {
+            Some(Concession { kind, amount: fetch_money(concession, CONCESSION_AMOUNT_KEY)?, ..Concession::default() })
+        }
+    }).collect()
+}
+
+fn save_concessions(concessions: &[Concession]) -> JsonValue {
+    let mut concessions_value = JsonValue::new_array();
+    for concession in concessions {
+        let mut concession_value = JsonValue::new_object();
+        concession_value[CONCESSION_KIND_KEY] = JsonValue::from(concession.kind.key());
+        if concession.kind.is_free_time() {
+            concession_value[CONCESSION_COUNT_KEY] = JsonValue::from(concession.count);
+        }
+        else {
+            concession_value[CONCESSION_AMOUNT_KEY] = JsonValue::from(concession.amount.as_f64());
+        }
+        let _ = concessions_value.push(concession_value);
+    }
+    concessions_value
+}
+
+fn build_status_history(data: &JsonValue) -> Vec<Transition> {
+    data.members().filter_map(|transition| {
+        Some(Transition {
+            status: Status::from_key(transition[TRANSITION_STATUS_KEY].as_str()?)?,
+            at: transition[TRANSITION_AT_KEY].as_u64()?,
+        })
+    }).collect()
+}
+
+fn save_status_history(history: &[Transition]) -> JsonValue {
+    let mut history_value = JsonValue::new_array();
+    for transition in history {
+        let mut transition_value = JsonValue::new_object();
+        transition_value[TRANSITION_STATUS_KEY] = JsonValue::from(transition.status.key());
+        transition_value[TRANSITION_AT_KEY] = JsonValue::from(transition.at);
+        let _ = history_value.push(transition_value);
+    }
+    history_value
+}
+
+fn build_filters(data: &JsonValue) -> Filters {
+    let ranges = data[FILTER_RANGES_KEY].members().filter_map(|range| {
+        Some(RangeFilter {
+            field: Fields::from_key(range[FILTER_FIELD_KEY].as_str()?)?,
+            min: range[FILTER_MIN_KEY].as_f64(),
+            max: range[FILTER_MAX_KEY].as_f64(),
+        })
+    }).collect();
+
+    // Files from before statuses existed show everything but archived listings
+    let statuses = &data[FILTER_STATUSES_KEY];
+    let statuses = if statuses.is_array() {
+        statuses.members().filter_map(|status| Status::from_key(status.as_str()?)).collect()
+    }
+    else {
+        Status::ACTIVE.to_vec()
+    };
+
+    Filters {
+        name: data[FILTER_NAME_KEY].as_str().unwrap_or_default().to_string(),
+        link: data[FILTER_LINK_KEY].as_str().unwrap_or_default().to_string(),
+        ranges,
+        statuses,
+    }
+}
+
+fn save_filters(filters: &Filters) -> JsonValue {
+    let mut ranges_value = JsonValue::new_array();
+    for range in &filters.ranges {
+        let mut range_value = JsonValue::new_object();
+        range_value[FILTER_FIELD_KEY] = JsonValue::from(range.field.key());
+        range_value[FILTER_MIN_KEY] = JsonValue::from(range.min);
+        range_value[FILTER_MAX_KEY] = JsonValue::from(range.max);
+        let _ = ranges_value.push(range_value);
+    }
+
+    let mut filters_value = JsonValue::new_object();
+    filters_value[FILTER_NAME_KEY] = JsonValue::from(filters.name.as_str());
+    filters_value[FILTER_LINK_KEY] = JsonValue::from(filters.link.as_str());
+    filters_value[FILTER_RANGES_KEY] = ranges_value;
+    filters_value[FILTER_STATUSES_KEY] = JsonValue::from(filters.statuses.iter().map(|status| status.key()).collect::<Vec<&str>>());
+    filters_value
+}
+
+fn save_split(policy: &SplitPolicy) -> JsonValue {
+    let mut split_value = JsonValue::new_object();
+    split_value[SPLIT_POLICY_KEY] = JsonValue::from(policy.key());
+
+    match policy {
+        SplitPolicy::Equal => {},
+        SplitPolicy::SquareFootage(values) | SplitPolicy::Shares(values) => {
+            split_value[SPLIT_VALUES_KEY] = JsonValue::from(values.clone());
+        },
+        SplitPolicy::Rooms(rooms) => {
+            let mut rooms_value = JsonValue::new_array();
+            for room in rooms {
+                let mut room_value = JsonValue::new_object();
+                room_value[ROOM_NAME_KEY] = JsonValue::from(room.name.as_str());
+                room_value[ROOM_RENT_KEY] = JsonValue::from(room.rent.as_f64());
+                let _ = rooms_value.push(room_value);
+            }
+            split_value[SPLIT_ROOMS_KEY] = rooms_value;
+        },
+    }
+
+    split_value
+}
+
+pub fn save(data: &Entry) -> Result<JsonValue, &str> {
+    let mut entry_value = JsonValue::new_object();
+
+    if entry_value.insert(NAME_KEY, data.get_name()).is_err() { return Err("Failed to save the name!"); }
+    if entry_value.insert(BED_KEY, data.get_i8(Fields::Beds)).is_err() { return Err("Failed to save bed count!"); }
+    if entry_value.insert(BATH_KEY, data.get_i8(Fields::Baths)).is_err() { return Err("Failed to save baths count!"); }
+    if entry_value.insert(DEPOSIT_KEY, data.get_money(Fields::Deposit).map(|amount| amount.as_f64())).is_err() { return Err("Failed to save deposit!"); }
+    if entry_value.insert(PET_DEPOSIT_KEY, data.get_money(Fields::PetDeposit).map(|amount| amount.as_f64())).is_err() { return Err("Failed to save pet deposit!"); }
+    if entry_value.insert(PET_DEPOSIT_PER_PET_KEY, data.is_pet_deposit_per_pet()).is_err() { return Err("Failed to save pet deposit scaling!"); }
+    if entry_value.insert(PET_FEE_KEY, data.get_money(Fields::PetFee).map(|amount| amount.as_f64())).is_err() { return Err("Failed to save pet fee!"); }
+    if entry_value.insert(MOVE_IN_FEES_KEY, data.get_money(Fields::MoveInFees).map(|amount| amount.as_f64())).is_err() { return Err("Failed to save move-in fees!"); }
+    if entry_value.insert(PET_MONTHLY_KEY, data.get_money(Fields::PetMonthly).map(|amount| amount.as_f64())).is_err() { return Err("Failed to save pet monthly!"); }
+    if entry_value.insert(PARKING_SPOTS_KEY, data.get_i8(Fields::ParkingSpots)).is_err() { return Err("Failed to save parking spots!"); }
+    if entry_value.insert(PARKING_MONTHLY_KEY, data.get_money(Fields::ParkingMonthly).map(|amount| amount.as_f64())).is_err() { return Err("Failed to save parking monthly!"); }
+    if entry_value.insert(MONTHLY_RENT_KEY, data.get_money(Fields::MonthlyRent).map(|amount| amount.as_f64())).is_err() { return Err("Failed to save monthly rent!"); }
+    if entry_value.insert(LEASE_MONTHS_KEY, data.get_i8(Fields::LeaseMonths)).is_err() { return Err("Failed to save lease length!"); }
+    if entry_value.insert(DEPOSIT_REFUND_KEY, data.get_f32(Fields::DepositRefund)).is_err() { return Err("Failed to save expected deposit refund!"); }
+    if entry_value.insert(LINK_KEY, data.get_link()).is_err() { return Err("Failed to save the link!"); }
+    if entry_value.insert(SPLIT_KEY, save_split(&data.get_split_policy())).is_err() { return Err("Failed to save the split policy!"); }
+    if entry_value.insert(LINE_ITEMS_KEY, save_line_items(&data.get_line_items())).is_err() { return Err("Failed to save the recurring fees!"); }
+    if entry_value.insert(CONCESSIONS_KEY, save_concessions(&data.get_concessions())).is_err() { return Err("Failed to save the concessions!"); }
+    if entry_value.insert(STATUS_KEY, data.get_status().key()).is_err() { return Err("Failed to save the status!"); }
+    if entry_value.insert(STATUS_HISTORY_KEY, save_status_history(&data.get_status_history())).is_err() { return Err("Failed to save the status history!"); }
+
+    Ok(entry_value)
+}
+
+pub(crate) fn save_properties(settings: &Settings) -> JsonValue {
+    let mut properties = JsonValue::new_object();
+    let mut roommates = JsonValue::new_array();
+    for roommate in &settings.roommates {
+        let mut roommate_value = JsonValue::new_object();
+        roommate_value[ROOMMATE_NAME_KEY] = JsonValue::from(roommate.name.as_str());
+        roommate_value[ROOMMATE_PETS_KEY] = JsonValue::from(roommate.pets);
+        roommate_value[ROOMMATE_PARKING_KEY] = JsonValue::from(roommate.needs_parking);
+        let _ = roommates.push(roommate_value);
+    }
+    properties[ROOMMATES_KEY] = roommates;
+    properties[CURRENCY_KEY] = JsonValue::from(settings.money_format.currency.code());
+    properties[LOCALE_KEY] = JsonValue::from(settings.money_format.locale.code());
+    properties[FILTERS_KEY] = save_filters(&settings.filters);
+    properties[BACKUP_COUNT_KEY] = JsonValue::from(settings.backup_count);
+    properties[migrations::VERSION_KEY] = JsonValue::from(migrations::CURRENT_VERSION);
+    properties[NEXT_ID_KEY] = JsonValue::from(settings.next_id);
+    properties
+}
+
+// The whole data file, with each listing keyed by its ID
+pub(crate) fn document(properties: JsonValue, listings: JsonValue) -> JsonValue {
+    let mut total = JsonValue::new_object();
+    total[PROPERTIES_KEY] = properties;
+    total[RENT_DATA_KEY] = listings;
+    total
+}
+
+pub(crate) fn save_document(settings: &Settings, entries: &[Entry], problems: &mut Vec<SaveError>) -> JsonValue {
+    let mut saveable = JsonValue::new_object();
+    for entry in entries {
+        let saved = save(entry).map_err(|error| error.to_string())
+            .and_then(|data| saveable.insert(&entry.get_id().to_string(), data).map_err(|error| error.to_string()));
+        if let Err(error) = saved {
+            problems.push(SaveError::Entry { entry: entry.get_name(), error });
+        }
+    }
+    document(save_properties(settings), saveable)
+}
+
+// Upgrades the document to the current format first, then keeps every listing that builds
+pub(crate) fn read_document(mut data: JsonValue) -> Loaded {
+    let mut loaded = Loaded::default();
+    if data.is_empty() { return loaded; }
+
+    let version = migrations::migrate(&mut data);
+    if version > migrations::CURRENT_VERSION {
+        loaded.report.problems.push(LoadError::NewerVersion(version));
+    }
+    else if version < migrations::CURRENT_VERSION {
+        loaded.report.upgraded_from = Some(version);
+    }
+
+    let properties = &data[PROPERTIES_KEY];
+    let settings = &mut loaded.settings;
+    if let Some(currency) = properties[CURRENCY_KEY].as_str().and_then(Currency::from_code) {
+        settings.money_format.currency = currency;
+    }
+    if let Some(locale) = properties[LOCALE_KEY].as_str().and_then(Locale::from_code) {
+        settings.money_format.locale = locale;
+    }
+
+    settings.filters = build_filters(&properties[FILTERS_KEY]);
+    settings.backup_count = properties[BACKUP_COUNT_KEY].as_i8().unwrap_or(settings.backup_count);
+
+    let roommates = &properties[ROOMMATES_KEY];
+    if roommates.is_array() && !roommates.is_empty() {
+        settings.roommates = roommates.members().map(|roommate| Roommate {
+            name: roommate[ROOMMATE_NAME_KEY].as_str().unwrap_or_default().to_string(),
+            pets: roommate[ROOMMATE_PETS_KEY].as_i8().unwrap_or(0),
+            needs_parking: roommate[ROOMMATE_PARKING_KEY].as_bool().unwrap_or(false),
+        }).collect();
+    }
+
+    let rental_data = &data[RENT_DATA_KEY];
+    settings.next_id = properties[NEXT_ID_KEY].as_u64().unwrap_or(1).max(1);
+    let mut unnumbered: Vec<&JsonValue> = Vec::new();
+    for (key, data) in rental_data.entries() {
+        let Ok(id) = key.parse::<u64>() else {
+            unnumbered.push(data);
+            continue;
+        };
+        settings.next_id = settings.next_id.max(id + 1);
+        match build(id, data) {
+            Ok(entry) => { loaded.entries.push(entry); },
+            Err(error) => { loaded.report.problems.push(error); },
+        }
+    }
+
+    // Hand-edited files might not use numbers as keys, so those get fresh IDs
+    for data in unnumbered {
+        match build(settings.next_id, data) {
+            Ok(entry) => { loaded.entries.push(entry); },
+            Err(error) => { loaded.report.problems.push(error); },
+        }
+        settings.next_id += 1;
+    }
+
+    loaded
+}
+
+#[derive(Default)]
+pub struct LoadReport {
+    pub problems: Vec<LoadError>,
+    pub quarantined: Option<PathBuf>,
+    pub upgraded_from: Option<i32>,
+}
+
+impl LoadReport {
+    pub fn is_fatal(&self) -> bool {
+        self.problems.iter().any(|problem| problem.is_fatal())
+    }
+}
+
+// Everything that is saved in a workspace, plus the edit history since it was opened
+pub struct Portfolio {
+    storage: Box<dyn Storage>,
+    list: Vec<Entry>,
+    settings: Settings,
+    history: History,
+    changes: Changes,
+    dirty: bool,
+}
+
+impl Portfolio {
+    pub fn new(storage: Box<dyn Storage>) -> Self {
+        Self {
+            storage,
+            list: Vec::new(),
+            settings: Settings::default(),
+            history: History::default(),
+            changes: Changes::default(),
+            dirty: false,
+        }
+    }
+
+    // The storage is picked from the file extension
+    pub fn open(path: PathBuf) -> (Self, LoadReport) {
+        let mut portfolio = Portfolio::new(storage::open(path));
+        let report = portfolio.reload();
+        (portfolio, report)
+    }
+
+    // For looking without saving, a file with problems is left where it is
+    pub fn open_read_only(path: PathBuf) -> (Self, LoadReport) {
+        let mut portfolio = Portfolio::new(storage::open_read_only(path));
+        let report = portfolio.reload();
+        (portfolio, report)
+    }
+
+    pub fn get_path(&self) -> &Path {
+        self.storage.get_path()
+    }
+
+    // Saving as moves the workspace, the next save writes all of it to the new file
+    pub fn set_path(&mut self, path: PathBuf) {
+        self.storage = storage::open(path);
+        self.changes.everything = true;
+        self.dirty = true;
+    }
+
+    // Writes every listing on the next save, not just the ones edited since the last
+    pub fn rewrite(&mut self) {
+        self.changes.everything = true;
+        self.dirty = true;
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.list
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Entry> {
+        self.list.iter().find(|entry| entry.is(id))
+    }
+
+    pub fn get_roommates(&self) -> &[Roommate] {
+        &self.settings.roommates
+    }
+
+    pub fn set_roommates(&mut self, roommates: Vec<Roommate>) {
+        self.settings.roommates = roommates;
+        self.calculate();
+        self.settings_changed();
+    }
+
+    pub fn get_money_format(&self) -> MoneyFormat {
+        self.settings.money_format
+    }
+
+    pub fn set_money_format(&mut self, money_format: MoneyFormat) {
+        self.settings.money_format = money_format;
+        self.settings_changed();
+    }
+
+    pub fn get_filters(&self) -> &Filters {
+        &self.settings.filters
+    }
+
+    pub fn set_filters(&mut self, filters: Filters) {
+        self.settings.filters = filters;
+        self.settings_changed();
+    }
+
+    pub fn get_backup_count(&self) -> i8 {
+        self.settings.backup_count
+    }
+
+    pub fn set_backup_count(&mut self, backup_count: i8) {
+        self.settings.backup_count = backup_count;
+        self.settings_changed();
+    }
+
+    // Settings are small enough that every save writes them
+    fn settings_changed(&mut self) {
+        self.dirty = true;
+    }
+
+    fn entry_changed(&mut self, id: u64) {
+        self.changes.entries.insert(id);
+        self.dirty = true;
+    }
+
+    // The entries that pass the filters, in the given order
+    pub fn visible(&self, order: &SortOrder) -> Vec<Entry> {
+        let mut visible: Vec<Entry> = self.list.iter().filter(|entry| self.settings.filters.matches(entry)).cloned().collect();
+        visible.sort_by(|a, b| order.compare(a, b));
+        visible
+    }
+
+    pub fn sorted(&self, order: &SortOrder) -> Vec<Entry> {
+        let mut sorted = self.list.clone();
+        sorted.sort_by(|a, b| order.compare(a, b));
+        sorted
+    }
+
+    pub fn calculate(&mut self) {
+        for entry in self.list.iter_mut() {
+            entry.calculate(&self.settings.roommates);
+        }
+    }
+
+    // Gives the entry the next free ID and returns it
+    pub fn add(&mut self, mut entry: Entry) -> u64 {
+        let id = self.settings.next_id;
+        self.settings.next_id += 1;
+        entry.set_id(id);
+        entry.calculate(&self.settings.roommates);
+
+        self.history.record(Command::Add { index: self.list.len(), entry: entry.clone() });
+        self.list.push(entry);
+        self.settings_changed();
+        self.entry_changed(id);
+        id
+    }
+
+    pub fn new_entry(&mut self) -> u64 {
+        let mut entry = Entry::default();
+        entry.set_name(format!("New_{}", self.settings.next_id));
+        entry.start_status(time::now_seconds());
+        self.add(entry)
+    }
+
+    // Every change to an entry goes through here, so it can be undone
+    pub fn update(&mut self, id: u64, what: &str, change: impl FnOnce(&mut Entry)) -> bool {
+        let Some(entry) = self.list.iter_mut().find(|entry| entry.is(id)) else { return false; };
+
+        let before = entry.clone();
+        change(entry);
+        entry.set_id(id);
+        entry.calculate(&self.settings.roommates);
+        self.history.record(Command::Edit { what: what.to_string(), before: Box::new(before), after: Box::new(entry.clone()) });
+        self.entry_changed(id);
+        true
+    }
+
+    pub fn remove(&mut self, id: u64) -> Option<Entry> {
+        let index = self.list.iter().position(|entry| entry.is(id))?;
+        let entry = self.list.remove(index);
+        self.history.record(Command::Remove { index, entry: entry.clone() });
+        self.entry_changed(id);
+        Some(entry)
+    }
+
+    // One undo step takes back the whole import. Returns how many entries were added or updated
+    pub fn merge(&mut self, plan: Vec<RowPlan>) -> usize {
+        let now = time::now_seconds();
+        let mut commands: Vec<Command> = Vec::new();
+        for row in plan {
+            match row {
+                RowPlan::New(mut entry) => {
+                    let id = self.settings.next_id;
+                    self.settings.next_id += 1;
+                    entry.set_id(id);
+                    entry.start_status(now);
+                    entry.calculate(&self.settings.roommates);
+                    commands.push(Command::Add { index: self.list.len(), entry: entry.clone() });
+                    self.list.push(entry);
+                    self.entry_changed(id);
+                },
+                RowPlan::Update(mut entry) => {
+                    let id = entry.get_id();
+                    let Some(existing) = self.list.iter_mut().find(|existing| existing.is(id)) else { continue; };
+                    entry.calculate(&self.settings.roommates);
+                    commands.push(Command::Edit { what: String::from("import"), before: Box::new(existing.clone()), after: Box::new(entry.clone()) });
+                    *existing = entry;
+                    self.entry_changed(id);
+                },
+                RowPlan::Skip(_) | RowPlan::Invalid(_) => {},
+            }
+        }
+
+        // Nothing imported leaves nothing to undo
+        let count = commands.len();
+        if count == 0 { return 0; }
+        self.history.record(Command::Batch { what: format!("import of {} listing(s)", count), commands });
+        self.settings_changed();
+        count
+    }
+
+    // Undoing can touch any number of entries, so the next save writes all of them
+    pub fn undo(&mut self) -> Option<String> {
+        let label = self.history.undo(&mut self.list)?;
+        self.calculate();
+        self.changes.everything = true;
+        self.dirty = true;
+        Some(label)
+    }
+
+    pub fn redo(&mut self) -> Option<String> {
+        let label = self.history.redo(&mut self.list)?;
+        self.calculate();
+        self.changes.everything = true;
+        self.dirty = true;
+        Some(label)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    // Lets a toast tell whether its command is still the next one to undo
+    pub fn next_undo(&self) -> Option<u64> {
+        self.history.next_undo()
+    }
+
+    pub fn list_backups(&self) -> Vec<Backup> {
+        self.storage.list_backups()
+    }
+
+    pub fn restore_backup(&mut self, backup: &Path) -> Result<LoadReport, String> {
+        self.storage.restore_backup(backup, self.settings.backup_count)?;
+        Ok(self.reload())
+    }
+
+    // Writes even when some entries fail to save, so the problems only cost those entries
+    pub fn save(&mut self) -> Vec<SaveError> {
+        let problems = self.storage.save(&self.settings, &self.list, &self.changes);
+        // After a failed write the store can't tell what made it, so the next save writes everything
+        let failed = problems.iter().any(|problem| matches!(problem, SaveError::Write { .. } | SaveError::Blocked(_)));
+        self.changes = Changes { everything: failed, ..Changes::default() };
+        self.dirty = false;
+        problems
+    }
+
+    // Replaces everything with what is stored, starting a fresh history
+    pub fn reload(&mut self) -> LoadReport {
+        let loaded = self.storage.load();
+        self.settings = loaded.settings;
+        self.list = loaded.entries;
+        self.history = History::default();
+        self.dirty = false;
+        // An upgraded file is written out in full, but only once something else changes
+        self.changes = Changes { everything: loaded.report.upgraded_from.is_some(), ..Changes::default() };
+        self.calculate();
+        loaded.report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{with_rent, workspace};
+    use std::fs::{create_dir_all, read_dir, read_to_string, remove_dir_all, write};
+
+    #[test]
+    fn saved_portfolios_reopen_unchanged() {
+        let dir = workspace("store_reopen");
+        let (mut portfolio, _) = Portfolio::open(dir.join("rentdata.json"));
+        portfolio.add(with_rent("Oak", 120_000));
+        let elm = portfolio.add(with_rent("Elm", 90_000));
+        portfolio.remove(elm);
+        portfolio.set_roommates(vec![Roommate::default(); 3]);
+        assert!(portfolio.save().is_empty());
+
+        let (mut reopened, report) = Portfolio::open(dir.join("rentdata.json"));
+        assert!(report.problems.is_empty());
+        assert_eq!(reopened.entries().len(), 1);
+        assert_eq!(reopened.get_roommates().len(), 3);
+        assert_eq!(reopened.get(1).unwrap().get_money(Fields::Split(0)), Some(Money::from_cents(40_000)));
+        assert_eq!(reopened.new_entry(), 3);
+
+        let _ = remove_dir_all(dir);
+    }
+
+    #[test]
+    fn statuses_are_saved_and_archived_listings_hidden() {
+        let dir = workspace("store_status");
+        let (mut portfolio, _) = Portfolio::open(dir.join("rentdata.json"));
+        let oak = portfolio.add(with_rent("Oak", 120_000));
+        let elm = portfolio.add(with_rent("Elm", 90_000));
+        portfolio.update(elm, "status", |entry| entry.set_status(Status::Archived, 1_000));
+        assert!(portfolio.save().is_empty());
+
+        let (mut reopened, _) = Portfolio::open(dir.join("rentdata.json"));
+        assert_eq!(reopened.get(elm).unwrap().get_status_history(), vec![Transition { status: Status::Archived, at: 1_000 }]);
+        let ids = |portfolio: &Portfolio| portfolio.visible(&SortOrder::default()).iter().map(|entry| entry.get_id()).collect::<Vec<u64>>();
+        assert_eq!(ids(&reopened), [oak]);
+
+        let mut filters = reopened.get_filters().clone();
+        filters.set_shown(Status::Archived, true);
+        filters.set_shown(Status::Interested, false);
+        reopened.set_filters(filters);
+        assert_eq!(ids(&reopened), [elm]);
+
+        let _ = remove_dir_all(dir);
+    }
+
+    #[test]
+    fn new_and_imported_listings_start_their_status_history() {
+        let mut portfolio = Portfolio::new(storage::open(workspace("store_initial").join("rentdata.json")));
+        let created = portfolio.new_entry();
+        let mut applied = with_rent("Elm", 90_000);
+        applied.set_status(Status::Applied, 1_000);
+        portfolio.merge(vec![RowPlan::New(with_rent("Oak", 100_000)), RowPlan::New(applied)]);
+
+        let statuses = |id: u64| portfolio.get(id).unwrap().get_status_history().iter().map(|transition| transition.status).collect::<Vec<Status>>();
+        assert_eq!(statuses(created), [Status::Interested]);
+        assert_eq!(statuses(created + 1), [Status::Interested]);
+        assert_eq!(statuses(created + 2), [Status::Applied]);
+        assert!(portfolio.get(created).unwrap().get_status_history()[0].at > 0);
+    }
+
+    #[test]
+    fn imports_that_add_nothing_leave_no_undo() {
+        let mut portfolio = Portfolio::new(storage::open(workspace("store_empty_import").join("rentdata.json")));
+        assert_eq!(portfolio.merge(vec![RowPlan::Skip(String::from("Already listed")), RowPlan::Invalid(String::from("The name is empty"))]), 0);
+        assert!(!portfolio.can_undo());
+        assert!(!portfolio.is_dirty());
+    }
+
+    #[test]
+    fn filters_are_saved_with_the_workspace() {
+        let dir = workspace("store_filters");
+        let (mut portfolio, _) = Portfolio::open(dir.join("rentdata.json"));
+        let mut filters = Filters { name: String::from("oak"), link: String::from("example.com"), ..Filters::default() };
+        filters.set_range(RangeFilter { field: Fields::MonthlyRent, min: Some(900.0), max: Some(1500.5) });
+        filters.set_range(RangeFilter { field: Fields::Split(1), min: None, max: Some(700.0) });
+        filters.set_shown(Status::Archived, true);
+        portfolio.set_filters(filters.clone());
+        assert!(portfolio.save().is_empty());
+
+        let (reopened, report) = Portfolio::open(dir.join("rentdata.json"));
+        assert!(report.problems.is_empty());
+        assert!(reopened.get_filters() == &filters);
+
+        let _ = remove_dir_all(dir);
+    }
+
+    #[test]
+    fn updates_recalculate_and_undo() {
+        let mut portfolio = Portfolio::new(storage::open(workspace("store_undo").join("rentdata.json")));
+        let id = portfolio.add(with_rent("Oak", 100_000));
+
+        assert!(portfolio.update(id, "Monthly Rent", |entry| entry.set_money(Fields::MonthlyRent, Money::from_cents(150_000))));
+        assert_eq!(portfolio.get(id).unwrap().get_money(Fields::Split(1)), Some(Money::from_cents(75_000)));
+        assert!(!portfolio.update(id + 1, "Monthly Rent", |_| {}));
+
+        assert_eq!(portfolio.undo(), Some(String::from("Monthly Rent on Oak")));
+        assert_eq!(portfolio.get(id).unwrap().get_money(Fields::Split(1)), Some(Money::from_cents(50_000)));
+        assert!(portfolio.is_dirty());
+    }
+
+    #[test]
+    fn concessions_keep_whole_counts_and_cents() {
+        let data = jzon::parse(r#"[{"kind": "freeweeks", "amount": 2.0}, {"kind": "freemonths", "count": 1}, {"kind": "credit", "amount": 300.1}]"#).unwrap();
+        let concessions = build_concessions(&data);
+        assert_eq!(concessions.iter().map(|concession| concession.count).collect::<Vec<i8>>(), [2, 1, 0]);
+        assert_eq!(concessions[2].amount, Money::from_cents(30_010));
+
+        let saved = save_concessions(&concessions);
+        assert_eq!(saved[0][CONCESSION_COUNT_KEY].as_i8(), Some(2));
+        assert!(!saved[0].has_key(CONCESSION_AMOUNT_KEY));
+        assert!(build_concessions(&saved) == concessions);
+    }
+
+    #[test]
+    fn unreadable_files_are_set_aside() {
+        let dir = workspace("store_corrupt");
+        let path = dir.join("rentdata.json");
+        create_dir_all(&dir).unwrap();
+        write(&path, "{ not json").unwrap();
+
+        let (portfolio, report) = Portfolio::open(path.clone());
+        assert!(report.is_fatal());
+        assert!(portfolio.entries().is_empty());
+        assert!(!path.exists());
+        assert!(report.quarantined.unwrap().exists());
+
+        let _ = remove_dir_all(dir);
+    }
+
+    #[test]
+    fn unchanged_files_with_bad_listings_are_copied_once() {
+        let dir = workspace("store_copied_once");
+        let path = dir.join("rentdata.json");
+        create_dir_all(&dir).unwrap();
+        write(&path, r#"{ "rentdata": { "1": 5 } }"#).unwrap();
+
+        let (_, first) = Portfolio::open(path.clone());
+        let (_, second) = Portfolio::open(path.clone());
+        assert_eq!(first.problems.len(), 1);
+        assert_eq!(first.quarantined, second.quarantined);
+        let copies = read_dir(&dir).unwrap().filter(|entry| entry.as_ref().unwrap().path().extension().is_some_and(|extension| extension == "corrupt")).count();
+        assert_eq!(copies, 1);
+
+        let _ = remove_dir_all(dir);
+    }
+
+    #[test]
+    fn files_left_in_place_are_not_saved_over() {
+        let dir = workspace("store_blocked");
+        let path = dir.join("rentdata.json");
+        create_dir_all(&dir).unwrap();
+        write(&path, "{ not json").unwrap();
+
+        // Read only never sets a file aside, the same as when moving it fails
+        let (mut portfolio, report) = Portfolio::open_read_only(path.clone());
+        assert!(report.quarantined.is_none());
+        portfolio.new_entry();
+        assert!(matches!(portfolio.save().as_slice(), [SaveError::Blocked(_)]));
+        assert_eq!(read_to_string(&path).unwrap(), "{ not json");
+
+        write(&path, "{}").unwrap();
+        assert!(portfolio.reload().problems.is_empty());
+        portfolio.new_entry();
+        assert!(portfolio.save().is_empty());
+
+        let _ = remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,23 @@
+use std::{env, path::PathBuf, process};
+
+use crate::objects::entry::Entry;
+use crate::objects::fields::Fields;
+use crate::objects::money::Money;
+
+pub fn named(id: u64, name: &str) -> Entry {
+    let mut entry = Entry::default();
+    entry.set_id(id);
+    entry.set_name(String::from(name));
+    entry
+}
+
+pub fn with_rent(name: &str, cents: i64) -> Entry {
+    let mut entry = named(1, name);
+    entry.set_money(Fields::MonthlyRent, Money::from_cents(cents));
+    entry
+}
+
+// A directory for one test's files, unique to the name and the test run
+pub fn workspace(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("cost_analysis_{}_{}", name, process::id()))
+}
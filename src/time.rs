@@ -0,0 +1,61 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn now_seconds() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0)
+}
+
+// UTC "YYYYMMDD-HHMMSS", which also sorts in time order
+pub fn format_timestamp(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64;
+    let remainder = seconds % 86_400;
+
+    // Days since the epoch to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}", year, month, day, remainder / 3_600, remainder % 3_600 / 60, remainder % 60)
+}
+
+// Only what format_timestamp writes, "YYYYMMDD-HHMMSS" in ASCII digits
+pub fn is_stamp(stamp: &str) -> bool {
+    let bytes = stamp.as_bytes();
+    bytes.len() == 15 && bytes.iter().enumerate().all(|(index, byte)| if index == 8 { *byte == b'-' } else { byte.is_ascii_digit() })
+}
+
+// Anything that isn't a stamp is shown as it is
+pub fn readable_stamp(stamp: &str) -> String {
+    if !is_stamp(stamp) { return stamp.to_string(); }
+
+    format!("{}-{}-{} {}:{}:{} UTC", &stamp[0..4], &stamp[4..6], &stamp[6..8], &stamp[9..11], &stamp[11..13], &stamp[13..15])
+}
+
+pub fn readable_timestamp(seconds: u64) -> String {
+    readable_stamp(&format_timestamp(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps_are_utc_civil_dates() {
+        assert_eq!(format_timestamp(0), "19700101-000000");
+        assert_eq!(format_timestamp(951_782_400), "20000229-000000");
+        assert_eq!(format_timestamp(1_792_332_245), "20261018-140405");
+        assert_eq!(readable_timestamp(1_792_332_245), "2026-10-18 14:04:05 UTC");
+    }
+
+    #[test]
+    fn only_stamps_are_reformatted() {
+        assert!(is_stamp("20261018-142405"));
+        assert!(!is_stamp("2026101x-142405"));
+        assert_eq!(readable_stamp("2026101é-14240"), "2026101é-14240");
+    }
+}